@@ -1,4 +1,4 @@
-use std::{hint::black_box, path::PathBuf};
+use std::{hint::black_box, path::Path};
 
 use clap::Parser;
 use criterion::{criterion_group, criterion_main, Criterion};
@@ -7,43 +7,43 @@ use criterion::{criterion_group, criterion_main, Criterion};
 fn write(c: &mut Criterion) {
     let serie = bw::episodate::request_detail("breaking-bad");
     c.bench_function("write breaking bad", |b| b.iter(||{
-        black_box(&serie).write(PathBuf::from("./breaking-bad.bw"))
+        black_box(&serie).write(Path::new("./breaking-bad.bw"))
     }));
 }
 
 fn read_system_series(c: &mut Criterion) {
-    let mut args = bw::args::Cli::parse_from(&[""]);
+    let mut args = bw::cli::Cli::parse_from([""]);
     c.bench_function("read system series", |b| b.iter(||{
-        black_box(&mut args).app_mode()
+        black_box(&mut args).execute()
     }));
 }
 
 fn read_system_series_finished(c: &mut Criterion) {
-    let mut args = bw::args::Cli::parse_from(["","-i","f"]);
+    let mut args = bw::cli::Cli::parse_from(["","-i","f"]);
     c.bench_function("read system series finished", |b| b.iter(||{
-        black_box(&mut args).app_mode()
+        black_box(&mut args).execute()
     }));
 }
 
 fn read_system_series_all(c: &mut Criterion) {
-    let mut args = bw::args::Cli::parse_from(["","-i", "a"]);
+    let mut args = bw::cli::Cli::parse_from(["","-i", "a"]);
     c.bench_function("read system series all", |b| b.iter(||{
-        black_box(&mut args).app_mode()
+        black_box(&mut args).execute()
     }));
 }
 
 fn search_system_series(c: &mut Criterion) {
-    let mut args = bw::args::Cli::parse_from(["","-s", "invincible"]);
+    let mut args = bw::cli::Cli::parse_from(["","-p", "name"]);
     c.bench_function("search system series", |b| b.iter(||{
-        black_box(&mut args).app_mode()
+        black_box(&mut args).execute()
     }));
 }
 
 fn path_system_series(c: &mut Criterion) {
-    let mut args = bw::args::Cli::parse_from(["bw","/home/nima/.cache/bingewatcher/Rick and Morty.bw"]);
+    let mut args = bw::cli::Cli::parse_from(["bw","/home/nima/.cache/bingewatcher/Rick and Morty.bw"]);
     assert!(!args.files.is_empty());
     c.bench_function("path system series", |b| b.iter(||{
-        black_box(&mut args).app_mode()
+        black_box(&mut args).execute()
     }));
 }
 
@@ -0,0 +1,281 @@
+use serde_json::{json, Value};
+use std::{fmt, io::Read};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    cli::Include,
+    serie::{self, Serie},
+};
+
+/// A request to the http api
+#[derive(Debug, PartialEq)]
+pub enum ApiRequest {
+    /// `GET /series`, optionally with `?include=all`
+    List(Option<Include>),
+    /// `GET /series/NAME`
+    Get(String),
+    /// `POST /series/NAME/watch`, with an optional `{"count": 2}`
+    Watch(String, usize),
+    /// `POST /series/NAME/unwatch`, with an optional `{"count": 2}`
+    Unwatch(String, usize),
+    /// `POST /series/NAME/seek`, with `{"episode": "S01E05"}`
+    Seek(String, String),
+    /// `POST /series`, with `{"source": "episodate", "id": "breaking-bad"}`
+    Add { source: String, id: String },
+}
+
+/// A serie as the api returns it, with what's derived from its seasons
+pub fn serie_json(serie: &Serie) -> Value {
+    let mut json = serde_json::to_value(serie).unwrap_or_default();
+    json["next_episode"] = json!(serie.next_episode_str());
+    json["watched"] = json!(serie.total_watched());
+    json["episodes"] = json!(serie.total_episodes());
+    json["finished"] = json!(serie.is_finished());
+    json["state"] = json!(serie.state().as_str());
+    json
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ApiError {
+    Unauthorized,
+    NotFound,
+    MethodNotAllowed,
+    BadRequest(String),
+    TooLarge,
+}
+
+impl ApiError {
+    pub fn status(&self) -> u16 {
+        match self {
+            ApiError::Unauthorized => 401,
+            ApiError::NotFound => 404,
+            ApiError::MethodNotAllowed => 405,
+            ApiError::BadRequest(_) => 400,
+            ApiError::TooLarge => 413,
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiError::Unauthorized => write!(f, "Missing or wrong token"),
+            ApiError::NotFound => write!(f, "No such endpoint"),
+            ApiError::MethodNotAllowed => write!(f, "Method not allowed on this endpoint"),
+            ApiError::BadRequest(message) => write!(f, "{message}"),
+            ApiError::TooLarge => write!(f, "Body is over {MAX_BODY} bytes"),
+        }
+    }
+}
+
+/// Bodies only carry a few small fields, so anything bigger isn't read
+const MAX_BODY: u64 = 64 * 1024;
+
+/// Reads a body of up to [`MAX_BODY`] bytes
+pub(crate) fn read_body(reader: impl Read) -> Result<Vec<u8>, ApiError> {
+    let mut body = vec![];
+    reader
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    if body.len() as u64 > MAX_BODY {
+        return Err(ApiError::TooLarge);
+    }
+    Ok(body)
+}
+
+/// Decodes the `%20`-like escapes of a url
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[inline(always)]
+fn body_json(body: &[u8]) -> Result<Value, ApiError> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(Value::Null);
+    }
+    serde_json::from_slice(body).map_err(|e| ApiError::BadRequest(format!("Body is not json: {e}")))
+}
+
+fn count_of(body: &Value) -> Result<usize, ApiError> {
+    match body.get("count") {
+        None => Ok(1),
+        Some(count) => count
+            .as_u64()
+            .map(|count| count as usize)
+            .ok_or(ApiError::BadRequest("\"count\" must be a positive number".to_string())),
+    }
+}
+
+fn string_of<'a>(body: &'a Value, key: &str) -> Result<&'a str, ApiError> {
+    body.get(key)
+        .and_then(Value::as_str)
+        .ok_or(ApiError::BadRequest(format!("Missing \"{key}\"")))
+}
+
+/// Parses a request from its method, url and body
+pub fn route(method: &str, url: &str, body: &[u8]) -> Result<ApiRequest, ApiError> {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let segments: Vec<String> = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode(segment).ok_or(ApiError::BadRequest(format!("Malformed url {url}"))))
+        .collect::<Result<_, _>>()?;
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    match (method, segments.as_slice()) {
+        ("GET", ["series"]) => {
+            let include = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("include="))
+                .map(|value| {
+                    clap::ValueEnum::from_str(value, true)
+                        .map_err(|_| ApiError::BadRequest(format!("Unknown include \"{value}\"")))
+                })
+                .transpose()?;
+            Ok(ApiRequest::List(include))
+        }
+        ("POST", ["series"]) => {
+            let body = body_json(body)?;
+            Ok(ApiRequest::Add {
+                source: string_of(&body, "source")?.to_string(),
+                id: string_of(&body, "id")?.to_string(),
+            })
+        }
+        ("GET", ["series", name]) => Ok(ApiRequest::Get(name.to_string())),
+        ("POST", ["series", name, "watch"]) => Ok(ApiRequest::Watch(name.to_string(), count_of(&body_json(body)?)?)),
+        ("POST", ["series", name, "unwatch"]) => Ok(ApiRequest::Unwatch(name.to_string(), count_of(&body_json(body)?)?)),
+        ("POST", ["series", name, "seek"]) => {
+            let body = body_json(body)?;
+            let episode = string_of(&body, "episode")?;
+            // absolute numbers depend on the serie, so they are looked up with it
+            if serie::parse_episode(episode).is_none() && episode.trim().parse::<usize>().is_err() {
+                return Err(ApiError::BadRequest(format!("\"{episode}\" is not an episode like S01E05 or 1071")));
+            }
+            Ok(ApiRequest::Seek(name.to_string(), episode.to_string()))
+        }
+        (_, ["series"] | ["series", _] | ["series", _, "watch" | "unwatch" | "seek"]) => Err(ApiError::MethodNotAllowed),
+        _ => Err(ApiError::NotFound),
+    }
+}
+
+/// Whether the `Authorization` header carries `token`. Without a token everything is allowed
+pub fn authorized(token: Option<&str>, authorization: Option<&str>) -> bool {
+    let Some(token) = token else {
+        return true;
+    };
+    let Some(given) = authorization.and_then(|header| header.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    // compare every byte, so the time taken doesn't tell how much of the token was right
+    given.len() == token.len() && given.bytes().zip(token.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[inline(always)]
+fn header(request: &Request, field: &'static str) -> Option<String> {
+    request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv(field))
+        .map(|header| header.value.to_string())
+}
+
+/// Listens on `address`, answering every request with the status code and json `on_request`
+/// returns for it. Requests must carry `Authorization: Bearer TOKEN` when `token` is set
+pub fn serve(
+    address: &str,
+    token: Option<&str>,
+    mut on_request: impl FnMut(ApiRequest) -> (u16, Value),
+) -> Result<(), String> {
+    let server = Server::http(address).map_err(|e| format!("Couldn't listen on {address}: {e}"))?;
+    eprintln!("INFO: Serving the api on http://{}", server.server_addr());
+    for mut request in server.incoming_requests() {
+        let result = if !authorized(token, header(&request, "Authorization").as_deref()) {
+            Err(ApiError::Unauthorized)
+        } else {
+            read_body(request.as_reader()).and_then(|body| route(request.method().as_str(), request.url(), &body))
+        };
+        let (status, json) = match result {
+            Ok(api_request) => on_request(api_request),
+            Err(e) => (e.status(), json!({"error": e.to_string()})),
+        };
+        let response = Response::from_string(json.to_string() + "\n")
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    #[test]
+    fn test_route() {
+        assert_eq!(route("GET", "/series", b""), Ok(ApiRequest::List(None)));
+        assert_eq!(route("GET", "/series?include=all", b""), Ok(ApiRequest::List(Some(Include::All))));
+        assert_eq!(route("GET", "/series/Breaking%20Bad", b""), Ok(ApiRequest::Get("Breaking Bad".to_string())));
+        assert_eq!(route("POST", "/series/Dark/watch", b""), Ok(ApiRequest::Watch("Dark".to_string(), 1)));
+        assert_eq!(route("POST", "/series/Dark/unwatch", br#"{"count":3}"#), Ok(ApiRequest::Unwatch("Dark".to_string(), 3)));
+        assert_eq!(route("POST", "/series/Dark/seek", br#"{"episode":"S02E05"}"#), Ok(ApiRequest::Seek("Dark".to_string(), "S02E05".to_string())));
+        assert_eq!(route("POST", "/series/One%20Piece/seek", br#"{"episode":"1071"}"#), Ok(ApiRequest::Seek("One Piece".to_string(), "1071".to_string())));
+        assert_eq!(
+            route("POST", "/series/", br#"{"source":"episodate","id":"dark"}"#),
+            Ok(ApiRequest::Add { source: "episodate".to_string(), id: "dark".to_string() })
+        );
+    }
+
+    #[test]
+    fn test_route_invalid() {
+        assert_eq!(route("GET", "/movies", b""), Err(ApiError::NotFound));
+        assert_eq!(route("DELETE", "/series/Dark", b""), Err(ApiError::MethodNotAllowed));
+        assert!(matches!(route("POST", "/series/Dark/watch", b"{"), Err(ApiError::BadRequest(_))));
+        assert!(matches!(route("POST", "/series/Dark/watch", br#"{"count":-1}"#), Err(ApiError::BadRequest(_))));
+        assert!(matches!(route("POST", "/series/Dark/seek", br#"{"episode":"pilot"}"#), Err(ApiError::BadRequest(_))));
+        assert!(matches!(route("GET", "/series?include=some", b""), Err(ApiError::BadRequest(_))));
+        assert!(matches!(route("GET", "/series/%zz", b""), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_serie_json() {
+        let serie = serie("8/8\n2/10", "Dark");
+        let json = serie_json(&serie);
+        assert_eq!(json["name"], "Dark");
+        assert_eq!(json["next_episode"], "S02E03");
+        assert_eq!(json["watched"], 10);
+        assert_eq!(json["episodes"], 18);
+        assert_eq!(json["state"], "watching");
+        assert_eq!(json["finished"], false);
+    }
+
+    #[test]
+    fn test_authorized() {
+        assert!(authorized(None, None));
+        assert!(authorized(Some("secret"), Some("Bearer secret")));
+        assert!(!authorized(Some("secret"), Some("Bearer secreT")));
+        assert!(!authorized(Some("secret"), Some("secret")));
+        assert!(!authorized(Some("secret"), None));
+    }
+
+    #[test]
+    fn test_read_body() {
+        assert_eq!(read_body(&b"{\"count\":2}"[..]), Ok(b"{\"count\":2}".to_vec()));
+        let body = vec![b' '; MAX_BODY as usize + 1];
+        assert_eq!(read_body(&body[..]), Err(ApiError::TooLarge));
+        assert!(read_body(&body[1..]).is_ok());
+    }
+}
@@ -1,19 +1,36 @@
 // vim:foldmethod=marker
 // imports{{{
 use crate::{
+    api::{self, ApiRequest},
+    config::{Config, ConfigTable},
     episodate,
-    serie::{PrintMode, Serie},
+    filter::{Expr, Selection},
+    html,
+    library::{self, Library},
+    lock::DirLock,
+    media,
+    notify,
+    picker,
+    scan,
+    serie::{self, Kind, Numbering, PrintMode, Serie, State, WatchUntil},
+    status::{self, Status},
+    tui::Tui,
     utils,
+    webhook,
+    wikipedia,
 };
+#[cfg(unix)]
+use crate::mpv;
 use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use clap_complete::Shell;
+use serde::{Deserialize, Serialize};
 use std::mem;
 use std::{
-    cmp::Ordering,
     fs,
-    io::{self, Write},
-    path::PathBuf,
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
     process,
+    time::Duration,
 };
 //}}}
 
@@ -21,17 +38,29 @@ use std::{
 #[command(author, version, about, long_about = None)]
 /// A minimal, file based cli tool that keeps track of the series you watch
 pub struct Cli {
-    /// Print current season of selected series
-    #[arg(short = 'p', long, default_value = "normal", global=true)]
-    pub print_mode: PrintMode,
+    /// Print current season of selected series [default: normal]
+    #[arg(short = 'p', long, global=true)]
+    pub print_mode: Option<PrintMode>,
 
-    /// Whether to include finished shows in searchs or not
-    #[arg(short, long, default_value = "no-finished", global=true)]
-    include: Include,
-
-    /// Whether to include finished shows in searchs or not
+    /// Which series to include by their state [default: no-finished]
     #[arg(short, long, global=true)]
-    hidden: bool,
+    include: Option<Include>,
+
+    /// Include hidden (dot-prefixed) series. --hidden=false turns off hidden = true from the config [default: false]
+    #[arg(short = 'H', long, global=true, num_args=0..=1, require_equals=true, default_missing_value="true")]
+    hidden: Option<bool>,
+
+    /// Only select series matching an expression, like "remaining<5 && tag:anime && !finished". Selects from all series unless --include is given
+    #[arg(short = 'w', long = "where", global=true)]
+    filter: Option<Expr>,
+
+    /// Take specials (season 0) into account for the next episode, watching and progress
+    #[arg(long, global=true)]
+    include_specials: bool,
+
+    /// Only select series with a tag. Can be repeated to require all of them
+    #[arg(long = "tag", global=true)]
+    tags: Vec<String>,
 
     /// Print shell completion
     #[command(subcommand)]
@@ -41,9 +70,37 @@ pub struct Cli {
     #[arg(short = 'n', long, global=true)]
     pub dry_run: bool,
 
-    /// Read all series from a directory (respects the BW_DIR variable)
-    #[arg(long, short, default_value=std::env::var_os("BW_DIR").unwrap_or(utils::append_home_dir(&[".cache", "bingewatcher"]).into_os_string()), global=true)]
-    pub directory: PathBuf,
+    /// Read all series from a directory (respects the BW_DIR variable) [default: $XDG_DATA_HOME/bingewatcher]
+    #[arg(long, short, global=true, conflicts_with = "profiles")]
+    pub directory: Option<PathBuf>,
+
+    /// Read series from the directory of a profile, defined in the config file. Can be repeated
+    #[arg(short = 'P', long = "profile", global=true)]
+    pub profiles: Vec<String>,
+
+    /// Config file to read defaults and profiles from (respects the BW_CONFIG variable)
+    #[arg(long, default_value=std::env::var_os("BW_CONFIG").unwrap_or(utils::config_dir().join("config.toml").into_os_string()), global=true)]
+    pub config: PathBuf,
+
+    /// Seconds to wait for other bw invocations to release the series directory [default: 10]
+    #[arg(long, global=true)]
+    pub lock_timeout: Option<u64>,
+
+    #[arg(skip)]
+    directories: Vec<PathBuf>,
+
+    #[arg(skip)]
+    player: Option<String>,
+
+    #[cfg(unix)]
+    #[arg(skip)]
+    mpv_threshold: Option<f64>,
+
+    #[arg(skip)]
+    api_token: Option<String>,
+
+    #[arg(skip)]
+    notify_command: Option<String>,
 
     /// Files to manipulate (overrides --directory and --include)
     #[arg(global=true)]
@@ -56,9 +113,16 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    /// Delete selected series
+    /// List selected series
     #[command(alias = "ls")]
-    List {},
+    List {
+        /// Order to list series in, ties broken by name
+        #[arg(short, long, default_value = "name")]
+        sort: Sort,
+        /// List in the opposite order
+        #[arg(short, long)]
+        reverse: bool,
+    },
     /// Delete selected series
     #[command(alias = "rm", alias = "del")]
     Delete,
@@ -74,6 +138,71 @@ pub enum Commands {
         #[arg()]
         count: usize,
     },
+    /// Set the next episode to watch, marking the ones before it as watched
+    Seek {
+        /// Episode in the S01E05 format, or its absolute number (like 1071) for series numbered absolutely
+        #[arg(required=true)]
+        episode: String,
+    },
+    /// Play the next episode from the media of the serie, and watch it if the player succeeds
+    Play {
+        /// Command to play the episode with [default: mpv]
+        #[arg(long)]
+        player: Option<String>,
+    },
+    /// Create or update series from the video files in a directory
+    Scan {
+        /// Directory of shows, like "Show Name/Season 02/Show.Name.S02E05.mkv"
+        #[arg(required=true)]
+        dir: PathBuf,
+    },
+    /// Browse and manage series in an interactive terminal ui
+    Tui,
+    /// Listen for Jellyfin, Plex or Kodi playback webhooks, and watch the episodes they finish
+    ServeWebhook {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8090")]
+        listen: String,
+    },
+    /// Notify about newly aired episodes of the series that have a source
+    Notify {
+        /// Command to notify with, getting the title and body as arguments [default: notify-send --app-name=bw]
+        #[arg(long)]
+        command: Option<String>,
+        /// Also add the new episodes to the series
+        #[arg(short, long)]
+        update: bool,
+    },
+    /// Print a summary of the series for status bars, like waybar or polybar
+    Status {
+        #[arg(short = 'F', long, default_value = "plain")]
+        format: status::Format,
+    },
+    /// Generate a static html page of the series, grouped by their state
+    Html {
+        /// Directory to write index.html (and local posters) to
+        #[arg(short, long, default_value = "site")]
+        output: PathBuf,
+    },
+    /// Serve a json api to list series and change their progress over http
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        listen: String,
+        /// Token requests need in their "Authorization: Bearer TOKEN" header [default: api-token from the config, if any]
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// Follow what mpv plays through its json ipc, and watch the episodes it plays to the end
+    #[cfg(unix)]
+    WatchMpv {
+        /// Socket mpv was started with, as in "mpv --input-ipc-server=/tmp/mpvsocket"
+        #[arg(short, long, default_value = "/tmp/mpvsocket")]
+        socket: PathBuf,
+        /// Percentage of an episode to play before it's watched [default: 90]
+        #[arg(short, long)]
+        threshold: Option<f64>,
+    },
     /// Generate shell completions
     Completions {
         #[arg(value_enum)]
@@ -85,7 +214,84 @@ pub enum Commands {
         #[command(subcommand)]
         command: OnlineCommands,
     },
-    /// Fetching series, from en.wikipedia.org
+    /// Write selected series to stdout as a library snapshot
+    Export {
+        #[arg(short = 'F', long, default_value = "json")]
+        format: library::Format,
+    },
+    /// Recreate series from a library snapshot
+    Import {
+        #[arg(short = 'F', long, default_value = "json")]
+        format: library::Format,
+        /// What to do when a serie already exists
+        #[arg(short, long, default_value = "skip")]
+        on_conflict: library::OnConflict,
+        /// Snapshot file to import
+        #[arg(required=true)]
+        file: PathBuf,
+    },
+    /// Rate a serie, or one of its seasons, from 1 to 10
+    Rate {
+        #[arg(required=true)]
+        name: String,
+        /// Rating from 1 to 10, or 0 to remove it
+        #[arg(required=true, value_parser = clap::value_parser!(u8).range(0..=10))]
+        rating: u8,
+        /// Rate this season (starting from 1) instead
+        #[arg(short, long)]
+        season: Option<usize>,
+    },
+    /// Write personal notes on a serie, or one of its seasons
+    Note {
+        #[arg(required=true)]
+        name: String,
+        /// Notes, or "" to remove them
+        #[arg(required=true)]
+        notes: String,
+        /// Write on this season (starting from 1) instead
+        #[arg(short, long)]
+        season: Option<usize>,
+    },
+    /// Watch a finished serie again from the start, recording that it was watched through
+    Rewatch {
+        #[arg(required=true)]
+        name: String,
+    },
+    /// Set how the episodes of a serie are numbered: by seasons, or absolute (like 1071)
+    Numbering {
+        #[arg(required=true)]
+        name: String,
+        #[arg(required=true)]
+        numbering: Numbering,
+    },
+    /// Set what a serie is: series, movie, miniseries or ova
+    Kind {
+        #[arg(required=true)]
+        name: String,
+        #[arg(required=true)]
+        kind: Kind,
+    },
+    /// Set the state of a serie: watching, paused, dropped, plan-to-watch or completed
+    State {
+        #[arg(required_unless_present = "migrate_hidden")]
+        name: Option<String>,
+        #[arg(required_unless_present = "migrate_hidden")]
+        state: Option<State>,
+        /// Unhide the hidden (dot-prefixed) series, making them paused instead
+        #[arg(long, conflicts_with_all = ["name", "state"])]
+        migrate_hidden: bool,
+    },
+    /// Organize series with tags
+    Tag {
+        #[command(subcommand)]
+        command: TagCommands,
+    },
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Fetching movies, from en.wikipedia.org
     Wikipedia {
         #[command(subcommand)]
         command: OnlineCommands,
@@ -115,14 +321,144 @@ pub enum OnlineCommands {
     },
 }
 
-#[derive(Debug, Clone, ValueEnum)]
-enum Include {
+#[derive(Subcommand, Debug)]
+pub enum TagCommands {
+    /// Add tags to a serie
+    Add {
+        #[arg(required=true)]
+        name: String,
+        /// Tag, or comma-separated tags
+        #[arg(required=true)]
+        tags: String,
+    },
+    /// Remove tags from a serie
+    #[command(alias = "rm")]
+    Remove {
+        #[arg(required=true)]
+        name: String,
+        /// Tag, or comma-separated tags
+        #[arg(required=true)]
+        tags: String,
+    },
+    /// Print the tags of the selected series, with how many series have each
+    #[command(alias = "ls")]
+    List,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommands {
+    /// Print the value of a key (e.g. print-mode or profiles.alice.directory)
+    Get {
+        #[arg(required=true)]
+        key: String,
+    },
+    /// Set the value of a key
+    Set {
+        #[arg(required=true)]
+        key: String,
+        #[arg(required=true)]
+        value: String,
+    },
+    /// Remove a key, restoring its default
+    Unset {
+        #[arg(required=true)]
+        key: String,
+    },
+    /// Print all the keys and their values
+    #[command(alias = "ls")]
+    List,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Include {
+    /// Watching and plan-to-watch series
     #[value(alias = "n")]
     NoFinished,
     #[value(alias = "a")]
     All,
+    /// Completed series
     #[value(alias = "f")]
     Finished,
+    #[value(alias = "w")]
+    Watching,
+    #[value(alias = "p")]
+    Paused,
+    #[value(alias = "d")]
+    Dropped,
+    #[value(alias = "planned")]
+    PlanToWatch,
+}
+
+impl Include {
+    #[inline]
+    pub fn matches(&self, serie: &Serie) -> bool {
+        match self {
+            Include::NoFinished => matches!(serie.state(), State::Watching | State::PlanToWatch),
+            Include::Finished => serie.state() == State::Completed,
+            Include::All => true,
+            Include::Watching => serie.state() == State::Watching,
+            Include::Paused => serie.state() == State::Paused,
+            Include::Dropped => serie.state() == State::Dropped,
+            Include::PlanToWatch => serie.state() == State::PlanToWatch,
+        }
+    }
+
+    #[inline]
+    pub fn next(&self) -> Self {
+        match self {
+            Include::NoFinished => Include::All,
+            Include::All => Include::Finished,
+            Include::Finished => Include::Watching,
+            Include::Watching => Include::Paused,
+            Include::Paused => Include::Dropped,
+            Include::Dropped => Include::PlanToWatch,
+            Include::PlanToWatch => Include::NoFinished,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum Sort {
+    #[value(alias = "n")]
+    Name,
+    /// Percentage of watched episodes
+    #[value(alias = "p")]
+    Progress,
+    /// Episodes left to watch
+    #[value(alias = "r")]
+    Remaining,
+    /// When the file last changed
+    #[value(alias = "m")]
+    Modified,
+    /// When an episode was last watched, from the last-watched line of the file
+    #[value(alias = "w")]
+    LastWatched,
+    /// When the file was created
+    #[value(alias = "a")]
+    Added,
+    /// Rating of the serie, unrated first
+    #[value(alias = "R")]
+    Rating,
+}
+
+impl Sort {
+    /// Sorts `series` by this key, then by name
+    pub fn sort(&self, series: &mut [(Serie, PathBuf)]) {
+        series.sort_by_cached_key(|(serie, _)| serie.name.to_lowercase());
+        match self {
+            Sort::Name => {}
+            Sort::Progress => series.sort_by(|(a, _), (b, _)| a.watched_percentage().total_cmp(&b.watched_percentage())),
+            Sort::Remaining => series.sort_by_key(|(serie, _)| serie.total_episodes().saturating_sub(serie.total_watched())),
+            Sort::Modified => series.sort_by_cached_key(|(_, path)| fs::metadata(path).and_then(|m| m.modified()).ok()),
+            // not every filesystem keeps creation times
+            Sort::Added => series.sort_by_cached_key(|(_, path)| {
+                fs::metadata(path).and_then(|m| m.created().or(m.modified())).ok()
+            }),
+            Sort::LastWatched => series.sort_by(|(a, _), (b, _)| a.last_watched.cmp(&b.last_watched)),
+            Sort::Rating => series.sort_by_key(|(serie, _)| serie.rating),
+        }
+    }
 }
 
 pub enum AppMode {
@@ -135,36 +471,30 @@ pub enum AppMode {
 
 macro_rules! call_series {
     ($self: expr, $series:expr, $func:ident $(, $arg:expr)*) => {
+        let include_specials = $self.include_specials;
         if $self.files.is_empty() {
-            if $self.hidden {
-                match $self.include {
-                    Include::NoFinished => $self.$func(&mut $series.by_ref().filter(|s_p| s_p.0.is_not_finished()) $(, $arg)*),
-                    Include::Finished => $self.$func(&mut $series.by_ref().filter(|s_p| s_p.0.is_finished()) $(, $arg)*),
-                    Include::All => $self.$func(&mut $series $(, $arg)*),
-                }
-            } else {
-                match $self.include {
-                    Include::NoFinished => $self.$func(&mut $series.by_ref().filter(|s_p| !s_p.1.file_name().unwrap().to_str().unwrap().starts_with('.') && s_p.0.is_not_finished()) $(, $arg)*),
-                    Include::Finished => $self.$func(&mut $series.by_ref().filter(|s_p| !s_p.1.file_name().unwrap().to_str().unwrap().starts_with('.') && s_p.0.is_finished()) $(, $arg)*),
-                    Include::All => $self.$func(&mut $series.by_ref().filter(|s_p| !s_p.1.file_name().unwrap().to_str().unwrap().starts_with('.')) $(, $arg)*),
-                }
-            }
+            let selection = $self.selection();
+            $self.$func(&mut $series.by_ref().map(move |(serie, path)| (serie.with_specials(include_specials), path)).filter(move |(serie, path)| selection.matches(serie, path)) $(, $arg)*)
         } else {
             let mut paths = std::mem::take(&mut $self.files);
-            $self.$func(paths.iter_mut().flat_map(|entry| Serie::from_file(entry).map(|serie| (serie, std::mem::take(entry)))) $(, $arg)*)
+            $self.$func(paths.iter_mut().flat_map(move |entry| Serie::from_file(entry).map(move |serie| (serie.with_specials(include_specials), std::mem::take(entry)))) $(, $arg)*)
         }
-    }
+    };
 }
 
 impl Cli {
     pub fn execute(&mut self) {
-        let mut series = utils::series_dir_reader(&self.directory).expect("Couldn't open dir");
+        if let Some(Commands::Config { ref command }) = self.command {
+            self.manage_config(command);
+            return;
+        }
+        self.load_config();
+        let mut series = utils::series_dirs_reader(&self.directories).expect("Couldn't open dir");
         let files_empty = self.files.is_empty();
 
         match self.command {
             Some(Commands::Completions { shell }) => {
                 utils::print_completions(shell, &mut Cli::command());
-                return;
             }
             Some(Commands::Episodate { ref mut command }) => match command {
                 OnlineCommands::Search { ref query } => {
@@ -188,7 +518,7 @@ impl Cli {
                     );
                 }
                 OnlineCommands::Detail { name } => {
-                    let serie = episodate::request_detail(&name);
+                    let serie = episodate::request_detail(name);
                     serie.print(&PrintMode::Extended, None);
                 }
             },
@@ -201,13 +531,430 @@ impl Cli {
             Some(Commands::Unwatch { count }) => {
                 call_series!(self, series, unwatch_series, count, !files_empty || self.force);
             }
-            Some(Commands::Wikipedia { .. }) => todo!(),
-            None | Some(Commands::List {}) => {
-                call_series!(self, series, list_series);
+            Some(Commands::Seek { ref episode }) => {
+                if serie::parse_episode(episode).is_none() && episode.trim().parse::<usize>().is_err() {
+                    exit_with_error(format!("\"{episode}\" is not an episode like S01E05 or 1071"));
+                }
+                let episode = episode.trim().to_string();
+                call_series!(self, series, seek_series, &episode, !files_empty || self.force);
+            }
+            Some(Commands::Play { ref player }) => {
+                let player = player.clone().or(self.player.take()).unwrap_or("mpv".to_string());
+                call_series!(self, series, play_series, &player, !files_empty || self.force);
+            }
+            Some(Commands::Scan { ref dir }) => {
+                self.scan_series(dir);
+            }
+            Some(Commands::ServeWebhook { ref listen }) => {
+                let result = webhook::serve(listen, |event| {
+                    self.watch_until(&event.show, event.season, event.episode)
+                });
+                if let Err(e) = result {
+                    exit_with_error(e);
+                }
+            }
+            Some(Commands::Notify { ref command, update }) => {
+                let command = command
+                    .clone()
+                    .or(self.notify_command.take())
+                    .unwrap_or("notify-send --app-name=bw".to_string());
+                // finished series can get new episodes too
+                self.include.get_or_insert(Include::All);
+                call_series!(self, series, notify_series, &command, update);
+            }
+            Some(Commands::Status { ref format }) => {
+                let format = format.clone();
+                // finished series count towards the summary too
+                self.include.get_or_insert(Include::All);
+                call_series!(self, series, status_series, &format);
+            }
+            Some(Commands::Html { ref output }) => {
+                let output = output.clone();
+                // the page groups finished series on its own
+                self.include.get_or_insert(Include::All);
+                call_series!(self, series, html_series, &output);
+            }
+            Some(Commands::Serve { ref listen, ref token }) => {
+                let token = token.clone().or(self.api_token.take());
+                if token.is_none() && !listen.starts_with("127.0.0.1:") && !listen.starts_with("localhost:") {
+                    eprintln!("WARNING: Serving on {listen} without a token lets anyone that reaches it change your series");
+                }
+                let result = api::serve(listen, token.as_deref(), |request| self.api_request(request));
+                if let Err(e) = result {
+                    exit_with_error(e);
+                }
+            }
+            #[cfg(unix)]
+            Some(Commands::WatchMpv { ref socket, threshold }) => {
+                let threshold = threshold.or(self.mpv_threshold).unwrap_or(90.0);
+                let result = mpv::watch(socket, threshold, mpv::POLL_INTERVAL, |path| {
+                    let Some((show, season, episode)) = scan::episode_of_path(Path::new(path)) else {
+                        eprintln!("WARNING: \"{path}\" has no episode like S01E05 in its name. Skipping it...");
+                        return;
+                    };
+                    self.watch_until(&show, season, episode);
+                });
+                if let Err(e) = result {
+                    exit_with_error(format!("Couldn't follow mpv at {}: {e}", socket.to_str().unwrap()));
+                }
             }
+            Some(Commands::Tui) => {
+                let tui = Tui::new(
+                    self.directories.clone(),
+                    self.include(),
+                    self.hidden(),
+                    self.lock_timeout(),
+                    self.dry_run,
+                );
+                if let Err(e) = tui.run() {
+                    exit_with_error(e);
+                }
+            }
+            Some(Commands::Export { ref format }) => {
+                let format = format.clone();
+                // a backup leaving finished or hidden series out would lose them
+                self.include.get_or_insert(Include::All);
+                self.hidden.get_or_insert(true);
+                call_series!(self, series, export_series, &format);
+            }
+            Some(Commands::Import { ref format, ref on_conflict, ref file }) => {
+                let library = Library::read(file, format).unwrap_or_else(|e| {
+                    eprintln!("ERROR: Couldn't read {}. Produced the following error:\n{}", file.to_str().unwrap(), e);
+                    process::exit(1);
+                });
+                self.import_series(library, on_conflict);
+            }
+            Some(Commands::Rate { ref name, rating, season }) => {
+                let rating = Some(rating).filter(|&rating| rating > 0);
+                self.annotate_serie(name, season, |own, _| mem::replace(own, rating) != rating);
+            }
+            Some(Commands::Note { ref name, ref notes, season }) => {
+                if notes.contains('\n') {
+                    exit_with_error("Notes can't have more than one line");
+                }
+                let notes = Some(notes.trim().to_string()).filter(|notes| !notes.is_empty());
+                self.annotate_serie(name, season, |_, own| mem::replace(own, notes.clone()) != notes);
+            }
+            Some(Commands::Rewatch { ref name }) => {
+                let result = self.modify_serie(name, |serie| {
+                    if !serie.rewatch() {
+                        return Err(format!("\"{}\" isn't finished yet", serie.name));
+                    }
+                    Ok(true)
+                });
+                if let Err((_, message)) = result {
+                    exit_with_error(message);
+                }
+            }
+            Some(Commands::Numbering { ref name, numbering }) => {
+                let result = self.modify_serie(name, |serie| {
+                    let numbering = Some(numbering).filter(|&numbering| numbering != Numbering::Seasons);
+                    Ok(mem::replace(&mut serie.numbering, numbering) != numbering)
+                });
+                match result {
+                    Ok((serie, false)) => eprintln!("INFO: \"{}\" is already numbered by {numbering}", serie.name),
+                    Ok(_) => {}
+                    Err((_, message)) => exit_with_error(message),
+                }
+            }
+            Some(Commands::Kind { ref name, kind }) => {
+                let result = self.modify_serie(name, |serie| {
+                    let kind = Some(kind).filter(|&kind| kind != Kind::Series);
+                    Ok(mem::replace(&mut serie.kind, kind) != kind)
+                });
+                match result {
+                    Ok((serie, false)) => eprintln!("INFO: \"{}\" is already of kind {kind}", serie.name),
+                    Ok(_) => {}
+                    Err((_, message)) => exit_with_error(message),
+                }
+            }
+            Some(Commands::State { migrate_hidden: true, .. }) => self.migrate_hidden(),
+            Some(Commands::State { ref name, state, .. }) => {
+                let (Some(name), Some(state)) = (name, state) else {
+                    unreachable!()
+                };
+                match self.modify_serie(name, |serie| Ok(serie.set_state(state))) {
+                    Ok((serie, false)) => eprintln!("INFO: \"{}\" is already {state}", serie.name),
+                    Ok(_) => {}
+                    Err((_, message)) => exit_with_error(message),
+                }
+            }
+            Some(Commands::Tag { command: TagCommands::List }) => {
+                self.include.get_or_insert(Include::All);
+                call_series!(self, series, list_tags);
+            }
+            Some(Commands::Tag { ref command }) => self.tag_serie(command),
+            Some(Commands::Config { .. }) => unreachable!(),
+            Some(Commands::Wikipedia { ref mut command }) => match command {
+                OnlineCommands::Search { ref query } => {
+                    let query = query.as_ref().map_or("", |v| v);
+                    let movies = wikipedia::search_movies(query).unwrap_or_else(|e| exit_with_error(e));
+                    for movie in movies {
+                        println!("{}", movie.key());
+                    }
+                }
+                OnlineCommands::Add {
+                    ref mut name,
+                    update,
+                } => {
+                    let name = mem::take(name);
+                    let update = *update;
+                    call_series!(
+                        self,
+                        series,
+                        add_online,
+                        |key| wikipedia::fetch_movie(key).unwrap_or_else(|e| exit_with_error(e)),
+                        name,
+                        update,
+                        files_empty
+                    );
+                }
+                OnlineCommands::Detail { name } => {
+                    let serie = wikipedia::fetch_movie(name).unwrap_or_else(|e| exit_with_error(e));
+                    serie.print(&PrintMode::Extended, None);
+                }
+            },
+            None => {
+                call_series!(self, series, list_series, Sort::Name, false);
+            }
+            Some(Commands::List { sort, reverse }) => {
+                call_series!(self, series, list_series, sort, reverse);
+            }
+        }
+    }
+
+    // precedence: command line options, then the first profile, then the config file
+    fn load_config(&mut self) {
+        let config = Config::read(&self.config).unwrap_or_else(|e| {
+            eprintln!("ERROR: Couldn't load config {}. Produced the following error:\n{}", self.config.to_str().unwrap(), e);
+            process::exit(1);
+        });
+        if let Some(directory) = self.directory.take() {
+            self.directories = vec![directory];
+        }
+        for name in &self.profiles {
+            let profile = config.profile(name).unwrap_or_else(|e| exit_with_error(e));
+            // the first profile decides the default options
+            if self.directories.is_empty() {
+                self.print_mode = self.print_mode.take().or(profile.print_mode.clone());
+                self.include = self.include.or(profile.include);
+                self.hidden = self.hidden.or(profile.hidden);
+            }
+            self.directories.push(profile.directory.clone());
+        }
+        if self.directories.is_empty() {
+            let directory = std::env::var_os("BW_DIR")
+                .map(PathBuf::from)
+                .or(config.directory)
+                .unwrap_or_else(|| {
+                    let directory = utils::default_directory();
+                    self.migrate_legacy_directory(&directory);
+                    directory
+                });
+            self.directories.push(directory);
+        }
+        self.print_mode = self.print_mode.take().or(config.print_mode);
+        self.include = self.include.or(config.include);
+        self.hidden = self.hidden.or(config.hidden);
+        self.lock_timeout = self.lock_timeout.or(config.lock_timeout);
+        self.player = config.player;
+        #[cfg(unix)]
+        {
+            self.mpv_threshold = config.mpv_threshold;
+        }
+        self.api_token = config.api_token;
+        self.notify_command = config.notify_command;
+    }
+
+    fn migrate_legacy_directory(&self, directory: &Path) {
+        let legacy = utils::legacy_directory();
+        if self.dry_run {
+            return;
+        }
+        match utils::migrate_directory(&legacy, directory) {
+            Ok(utils::Migration::Moved) => eprintln!(
+                "INFO: Moved your series from {} to {}",
+                legacy.to_str().unwrap(),
+                directory.to_str().unwrap()
+            ),
+            Ok(utils::Migration::BothHaveFiles) => eprintln!(
+                "WARNING: Both {} and {} have series. Using {}; move the others over by hand",
+                legacy.to_str().unwrap(),
+                directory.to_str().unwrap(),
+                directory.to_str().unwrap()
+            ),
+            Ok(utils::Migration::Nothing) => {}
+            Err(e) => eprintln!(
+                "WARNING: Couldn't move your series from {} to {}. Produced the following error:\n{}",
+                legacy.to_str().unwrap(),
+                directory.to_str().unwrap(),
+                e
+            ),
         }
     }
 
+    /// Hidden series were how series were put on hold before states. Renames them to not be
+    /// hidden, and makes the ones without a state paused
+    fn migrate_hidden(&self) {
+        let series = utils::series_dirs_reader(&self.directories).unwrap_or_else(|e| exit_with_error(e));
+        for (mut serie, path) in series.filter(|(_, path)| utils::is_hidden(path)) {
+            serie.name = serie.name.trim_start_matches('.').to_string();
+            let new_path = path.with_file_name(serie.filename());
+            if new_path.exists() {
+                eprintln!(
+                    "WARNING: Can't unhide {}, {} already exists. Skipping it...",
+                    path.to_str().unwrap(),
+                    new_path.to_str().unwrap()
+                );
+                continue;
+            }
+            if serie.state.is_none() {
+                serie.state = Some(State::Paused);
+            }
+            let _lock = self.lock(&path);
+            if self.dry_run {
+                eprintln!("Moved {} to {} (dry-run)", path.to_str().unwrap(), new_path.to_str().unwrap());
+                continue;
+            }
+            self.write_serie(&serie, &new_path);
+            if let Err(e) = fs::remove_file(&path) {
+                eprintln!(
+                    "ERROR: Couldn't delete {}. Produced the following error:\n{}",
+                    path.to_str().unwrap(),
+                    e
+                );
+                process::exit(1);
+            }
+            eprintln!("Moved {} to {}", path.to_str().unwrap(), new_path.to_str().unwrap());
+        }
+    }
+
+    fn manage_config(&self, command: &ConfigCommands) {
+        let mut table = ConfigTable::read(&self.config).unwrap_or_else(|e| exit_with_error(e));
+        match command {
+            ConfigCommands::Get { key } => match table.get(key).unwrap_or_else(|e| exit_with_error(e)) {
+                toml::Value::String(value) => println!("{value}"),
+                value => println!("{value}"),
+            },
+            ConfigCommands::List => {
+                for (key, value) in table.list() {
+                    println!("{key} = {value}");
+                }
+            }
+            ConfigCommands::Set { key, value } => {
+                table.set(key, value).unwrap_or_else(|e| exit_with_error(e));
+                self.write_config(&table);
+            }
+            ConfigCommands::Unset { key } => {
+                table.unset(key).unwrap_or_else(|e| exit_with_error(e));
+                self.write_config(&table);
+            }
+        }
+    }
+
+    fn write_config(&self, table: &ConfigTable) {
+        if self.dry_run {
+            print!("{table}");
+        } else if let Err(e) = table.write(&self.config) {
+            eprintln!(
+                "ERROR: Couldn't write {}. Produced the following error:\n{}",
+                self.config.to_str().unwrap(),
+                e
+            );
+            process::exit(1);
+        }
+    }
+
+    #[inline]
+    pub fn print_mode(&self) -> &PrintMode {
+        self.print_mode.as_ref().unwrap_or(&PrintMode::Normal)
+    }
+
+    #[inline]
+    fn hidden(&self) -> bool {
+        self.hidden.unwrap_or(false)
+    }
+
+    #[inline]
+    fn include(&self) -> Include {
+        match self.include {
+            Some(include) => include,
+            // the expression says what to select on its own
+            None if self.filter.is_some() => Include::All,
+            None => Include::NoFinished,
+        }
+    }
+
+    #[inline]
+    fn selection(&self) -> Selection {
+        Selection {
+            include: self.include(),
+            hidden: self.hidden(),
+            filter: self.filter.clone(),
+            tags: self.tags.clone(),
+        }
+    }
+
+    #[inline]
+    fn lock_timeout(&self) -> Duration {
+        Duration::from_secs(self.lock_timeout.unwrap_or(10))
+    }
+
+    /// Locks the directory of `path` for a read-modify-write, exiting if it can't. A dry run
+    /// doesn't lock, as it writes nothing (not even the lock file)
+    fn lock(&self, path: &Path) -> Option<DirLock> {
+        if self.dry_run {
+            return None;
+        }
+        let lock = DirLock::acquire(path, self.lock_timeout(), |lock_path| {
+            eprintln!(
+                "INFO: Another bw is using {}. Waiting for it...",
+                lock_path.to_str().unwrap()
+            )
+        });
+        Some(lock.unwrap_or_else(|e| exit_with_error(e)))
+    }
+
+    /// Like [`Cli::lock`], failing with an http-like status code instead of exiting
+    fn try_lock(&self, path: &Path) -> Result<Option<DirLock>, (u16, String)> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        DirLock::acquire(path, self.lock_timeout(), |_| {}).map(Some).map_err(|e| (503, e.to_string()))
+    }
+
+    /// Locks the directory of `path` and reads the serie again, so changes made by other bw
+    /// invocations since it was listed aren't lost
+    fn lock_and_reload(&self, path: &Path) -> Option<(Option<DirLock>, Serie)> {
+        let lock = self.lock(path);
+        match Serie::from_file(path) {
+            Some(serie) => Some((lock, serie.with_specials(self.include_specials))),
+            None => {
+                eprintln!("WARNING: {} is gone or malformed. Skipping it...", path.to_str().unwrap());
+                None
+            }
+        }
+    }
+
+    /// Directory new series are written to
+    #[inline]
+    pub fn directory(&self) -> &Path {
+        &self.directories[0]
+    }
+
+    /// Name of the profile a serie file belongs to, if more than one profile is selected
+    fn profile_of(&self, path: &Path) -> Option<&str> {
+        if self.profiles.len() < 2 {
+            return None;
+        }
+        let parent = path.parent()?;
+        self.profiles
+            .iter()
+            .zip(&self.directories)
+            .find(|(_, dir)| dir.as_path() == parent)
+            .map(|(name, _)| name.as_str())
+    }
+
     #[inline(always)]
     pub fn add_online(
         &mut self,
@@ -218,81 +965,560 @@ impl Cli {
         files_empty: bool,
     ) {
         let serie = fetch_function(&name);
-        if let Some((mut old_serie, path)) = series.find(|s_p| s_p.0.name == serie.name) {
-            if update {
-                eprintln!(
-                    "INFO: The serie \"{}\" already exists. Updating it...",
-                    serie.name
-                );
-                old_serie.merge_serie(&serie);
-                if !self.dry_run {
-                    old_serie.print(&self.print_mode, Some(&path));
-                    old_serie.write(path);
-                }
-            } else {
-                eprintln!("ERROR: The serie \"{}\" already exists.", serie.name);
-                process::exit(1);
-            }
-        } else {
-            if files_empty {
-                let path = self.directory.join(serie.filename());
-                serie.print(&self.print_mode, Some(&path));
-                if !self.dry_run {
-                    serie.write(path);
-                }
-            } else {
+        let path = match series.find(|s_p| s_p.0.name == serie.name) {
+            Some((_, path)) => path,
+            // the serie might exist, but be filtered out of the series
+            None if files_empty => self.directory().join(serie.filename()),
+            None => {
                 eprintln!("WARNING: Can't detect the file to write on. Writing on stdout...");
                 serie.print(&PrintMode::Content, None);
+                return;
             }
+        };
+        let _lock = self.lock(&path);
+        if !path.exists() {
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
+            return;
+        }
+        if !update {
+            eprintln!("ERROR: The serie \"{}\" already exists.", serie.name);
+            process::exit(1);
         }
+        eprintln!(
+            "INFO: The serie \"{}\" already exists. Updating it...",
+            serie.name
+        );
+        let Some(mut old_serie) = Serie::from_file(&path) else {
+            eprintln!("ERROR: {} is malformed.", path.to_str().unwrap());
+            process::exit(1);
+        };
+        old_serie.merge_serie(&serie);
+        old_serie.print(self.print_mode(), Some(&path));
+        self.write_serie(&old_serie, &path);
     }
 
     #[inline(always)]
-    fn write_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>) {
-        for (mut serie, path) in series {
-            serie.print(&self.print_mode, Some(&path));
-            if !self.dry_run {
-                serie.write(path).expect("Write failed");
+    fn write_serie(&self, serie: &Serie, path: &Path) {
+        if self.dry_run {
+            return;
+        }
+        if let Err(e) = serie.write(path) {
+            eprintln!(
+                "ERROR: Couldn't write {}. Produced the following error:\n{}",
+                path.to_str().unwrap(),
+                e
+            );
+            process::exit(1);
+        }
+    }
+
+    #[inline(always)]
+    fn export_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, format: &library::Format) {
+        let library = Library::from_series(series.map(|(serie, _)| serie));
+        let mut handle = io::stdout().lock();
+        library.write(&mut handle, format).expect("Writing to stdout failed");
+        let _ = writeln!(handle);
+    }
+
+    fn import_series(&self, library: Library, on_conflict: &library::OnConflict) {
+        if !self.dry_run {
+            let _ = fs::create_dir_all(self.directory());
+        }
+        for serie in library.series {
+            if let Some(reason) = Library::invalid(&serie) {
+                eprintln!("WARNING: Can't import \"{}\", {reason}. Skipping it...", serie.name);
+                continue;
             }
+            let path = self.directory().join(serie.filename());
+            let _lock = self.lock(&path);
+            let serie = match (Serie::from_file(&path), on_conflict) {
+                (Some(_), library::OnConflict::Skip) => {
+                    eprintln!("INFO: The serie \"{}\" already exists. Skipping it...", serie.name);
+                    continue;
+                }
+                (Some(mut old_serie), library::OnConflict::Merge) => {
+                    old_serie.merge_serie(&serie);
+                    old_serie
+                }
+                (Some(_), library::OnConflict::Replace) | (None, _) => serie,
+            };
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
         }
     }
 
-    // !files_empty is used as the force argument. this way we confirm only if files are empty
-    fn watch_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, count: usize, force: bool) {
-        for (mut serie, path) in series {
-            if !force {
-                let prompt = format!("Do you want to watch {count} episodes from \"{}\" [Y/n] ", serie.name);
-                if !yes_no_confirmation(prompt) {
+    /// Fetches a serie from its `source:`, counting only the episodes aired by today
+    fn fetch_aired(source: &str) -> Result<Serie, String> {
+        match source.split_once(':') {
+            Some(("episodate", permalink)) => episodate::fetch_aired(permalink, &utils::today()),
+            _ => Err(format!("Unknown source \"{source}\", expected one like \"episodate:breaking-bad\"")),
+        }
+    }
+
+    fn notify_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, command: &str, update: bool) {
+        for (serie, path) in series {
+            let Some(source) = &serie.source else {
+                continue;
+            };
+            // movies don't get new episodes
+            if serie.kind() == Kind::Movie {
+                continue;
+            }
+            let fresh = match Self::fetch_aired(source) {
+                Ok(fresh) => fresh,
+                Err(e) => {
+                    eprintln!("WARNING: Couldn't fetch \"{}\" from {source}: {e}. Skipping it...", serie.name);
+                    continue;
+                }
+            };
+            // the first check only records what aired so far, to announce what airs after it
+            let Some(announced) = serie.announced else {
+                let Some(last) = notify::last_episode(&fresh) else {
+                    continue;
+                };
+                eprintln!(
+                    "INFO: Announcing the episodes of \"{}\" aired after S{:02}E{:02}",
+                    serie.name, last.0, last.1
+                );
+                if self.dry_run {
+                    continue;
+                }
+                let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
                     continue;
+                };
+                serie.announced = Some(last);
+                if update {
+                    serie.merge_serie(&fresh);
                 }
+                self.write_serie(&serie, &path);
+                continue;
+            };
+            let episodes = notify::new_episodes(announced, &fresh);
+            let Some(&last) = episodes.last() else {
+                continue;
+            };
+            let (title, body) = notify::announcement(&serie.name, &episodes);
+            println!("{title}: {body}");
+            if self.dry_run {
+                continue;
             }
-            serie.watch(count);
-            serie.print(&self.print_mode, Some(&path));
-            if !self.dry_run {
-                serie.write(path).expect("Write failed");
+            if let Err(e) = notify::send(command, &title, &body) {
+                // not marked as announced, so the next run tries again
+                eprintln!("WARNING: Couldn't notify about \"{}\": {e}", serie.name);
+                continue;
             }
+            let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
+                continue;
+            };
+            serie.announced = Some(last);
+            if update {
+                serie.merge_serie(&fresh);
+            }
+            self.write_serie(&serie, &path);
+        }
+    }
+
+    fn tag_serie(&self, command: &TagCommands) {
+        let (TagCommands::Add { name, tags } | TagCommands::Remove { name, tags }) = command else {
+            unreachable!()
+        };
+        let tags: Vec<&str> = tags.split(',').map(str::trim).collect();
+        if tags.iter().any(|tag| tag.is_empty()) {
+            exit_with_error("Tags can't be empty");
+        }
+        let result = self.modify_serie(name, |serie| {
+            let mut changed = false;
+            for tag in &tags {
+                changed |= match command {
+                    TagCommands::Add { .. } => serie.add_tag(tag)?,
+                    _ => serie.remove_tag(tag),
+                };
+            }
+            Ok(changed)
+        });
+        match result {
+            Ok((serie, false)) => eprintln!("INFO: Nothing changed in the tags of \"{}\"", serie.name),
+            Ok(_) => {}
+            Err((_, message)) => exit_with_error(message),
+        }
+    }
+
+    /// Changes the rating and notes of a serie, or of its season `season`. `change` returns
+    /// whether it changed them
+    fn annotate_serie(
+        &self,
+        name: &str,
+        season: Option<usize>,
+        change: impl FnOnce(&mut Option<u8>, &mut Option<String>) -> bool,
+    ) {
+        let result = self.modify_serie(name, |serie| match season {
+            None => Ok(change(&mut serie.rating, &mut serie.notes)),
+            Some(number) if number == 0 || number > serie.seasons().len() => {
+                Err(format!("\"{}\" has no season {number}", serie.name))
+            }
+            Some(number) => {
+                let season = serie.season_mut(number).unwrap();
+                Ok(change(&mut season.rating, &mut season.notes))
+            }
+        });
+        match result {
+            Ok((serie, false)) => eprintln!("INFO: Nothing changed in \"{}\"", serie.name),
+            Ok(_) => {}
+            Err((_, message)) => exit_with_error(message),
+        }
+    }
+
+    fn list_tags(&self, series: impl Iterator<Item = (Serie, PathBuf)>) {
+        let mut counts: std::collections::BTreeMap<String, usize> = Default::default();
+        for (serie, _) in series {
+            for tag in serie.tags {
+                *counts.entry(tag.to_lowercase()).or_default() += 1;
+            }
+        }
+        for (tag, count) in counts {
+            println!("{tag} {count}");
+        }
+    }
+
+    #[inline(always)]
+    fn status_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, format: &status::Format) {
+        let status = Status::from_series(series.map(|(serie, _)| serie));
+        println!("{}", status.format(format));
+    }
+
+    fn html_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, output: &Path) {
+        let posters = output.join("posters");
+        let series: Vec<Serie> = series
+            .map(|(mut serie, path)| {
+                // local posters are copied next to the page, so it can be published on its own.
+                // Relative ones are relative to the serie file, like media
+                let parent = path.parent().unwrap_or(Path::new(""));
+                let local = serie
+                    .poster
+                    .as_deref()
+                    .filter(|poster| !poster.contains("://"))
+                    .map(|poster| parent.join(poster))
+                    .filter(|poster| poster.is_file());
+                if let Some(poster) = local {
+                    let extension = poster.extension().and_then(|ext| ext.to_str()).unwrap_or("jpg");
+                    let file_name = format!("{}.{extension}", serie.name);
+                    if !self.dry_run {
+                        let copied = fs::create_dir_all(&posters).and_then(|_| fs::copy(&poster, posters.join(&file_name)));
+                        if let Err(e) = copied {
+                            eprintln!("WARNING: Couldn't copy the poster of \"{}\": {e}", serie.name);
+                        }
+                    }
+                    serie.poster = Some(format!("posters/{file_name}"));
+                }
+                serie
+            })
+            .collect();
+        let path = output.join("index.html");
+        if self.dry_run {
+            eprintln!("Wrote {} series to {} (dry-run)", series.len(), path.to_str().unwrap());
+            return;
+        }
+        let written = fs::create_dir_all(output).and_then(|_| utils::write_atomic(&path, html::render(&series).as_bytes()));
+        if let Err(e) = written {
+            eprintln!("ERROR: Couldn't write {}. Produced the following error:\n{}", path.to_str().unwrap(), e);
+            process::exit(1);
+        }
+        eprintln!("Wrote {} series to {}", series.len(), path.to_str().unwrap());
+    }
+
+    fn watch_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, count: usize, force: bool) {
+        let series = select_series(series, force, &format!("watch {count} episodes from"), |serie| {
+            format!("Do you want to watch {count} episodes from \"{}\" [Y/n] ", serie.name)
+        });
+        for (_, path) in series {
+            let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
+                continue;
+            };
+            serie.watch(count);
+            serie.mark_watched();
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
         }
     }
 
     fn unwatch_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, count: usize, force: bool) {
-        for (mut serie, path) in series {
-            if !force {
-                let prompt = format!("Do you want to unwatch {count} episodes from \"{}\" [Y/n] ", serie.name);
-                if !yes_no_confirmation(prompt) {
-                    continue;
+        let series = select_series(series, force, &format!("unwatch {count} episodes from"), |serie| {
+            format!("Do you want to unwatch {count} episodes from \"{}\" [Y/n] ", serie.name)
+        });
+        for (_, path) in series {
+            let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
+                continue;
+            };
+            serie.unwatch(count);
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
+        }
+    }
+
+    fn seek_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, episode: &str, force: bool) {
+        let absolute = serie::parse_episode(episode).is_none();
+        let target = if absolute { format!("absolute episode {episode}") } else { episode.to_string() };
+        let series = select_series(series, force, &format!("seek to {target}"), |serie| {
+            format!("Do you want to seek \"{}\" to {target} [Y/n] ", serie.name)
+        });
+        for (_, path) in series {
+            let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
+                continue;
+            };
+            if absolute && serie.numbering() != Numbering::Absolute {
+                eprintln!(
+                    "WARNING: \"{}\" isn't numbered absolutely, so it has no {target}. Seek to one like S01E05, or set \"bw numbering NAME absolute\". Skipping it...",
+                    serie.name
+                );
+                continue;
+            }
+            let found = serie.find_episode(episode);
+            if !found.is_some_and(|(season, episode)| serie.seek(season, episode)) {
+                eprintln!("WARNING: \"{}\" has no {target}. Skipping it...", serie.name);
+                continue;
+            }
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
+        }
+    }
+
+    /// Changes the serie named `show` (exactly, ignoring case) under its lock, for requests of
+    /// other programs. `change` returns whether there is anything to write, or why the serie can't
+    /// be changed like that. Fails with an http-like status code and a message
+    fn modify_serie(
+        &self,
+        show: &str,
+        change: impl FnOnce(&mut Serie) -> Result<bool, String>,
+    ) -> Result<(Serie, bool), (u16, String)> {
+        let (_, path) = self.find_serie(show)?;
+        self.modify_serie_at(&path, change)
+    }
+
+    /// Like [`Cli::modify_serie`], for the serie at `path`
+    fn modify_serie_at(
+        &self,
+        path: &Path,
+        change: impl FnOnce(&mut Serie) -> Result<bool, String>,
+    ) -> Result<(Serie, bool), (u16, String)> {
+        let _lock = self.try_lock(path)?;
+        let Some(mut serie) = Serie::from_file(path).map(|serie| serie.with_specials(self.include_specials)) else {
+            return Err((404, format!("{} is gone or malformed", path.to_str().unwrap())));
+        };
+        if !change(&mut serie).map_err(|e| (422, e))? {
+            return Ok((serie, false));
+        }
+        serie.print(self.print_mode(), Some(&path.to_path_buf()));
+        if !self.dry_run {
+            if let Err(e) = serie.write(path) {
+                eprintln!("ERROR: Couldn't write {}. Produced the following error:\n{}", path.to_str().unwrap(), e);
+                return Err((500, e.to_string()));
+            }
+        }
+        Ok((serie, true))
+    }
+
+    /// The serie named `show` (exactly, ignoring case), without locking it. When there's none,
+    /// the message suggests the serie `show` is part of the name of, if only one
+    fn find_serie(&self, show: &str) -> Result<(Serie, PathBuf), (u16, String)> {
+        let series = || utils::series_dirs_reader(&self.directories).map_err(|e| (500, e.to_string()));
+        if let Some((serie, path)) = utils::find_serie_exact(series()?, show) {
+            return Ok((serie.with_specials(self.include_specials), path));
+        }
+        Err(match utils::find_serie(series()?, show) {
+            Some((serie, _)) => (404, format!("No serie is named \"{show}\". Did you mean \"{}\"?", serie.name)),
+            None => (404, format!("No serie is named \"{show}\"")),
+        })
+    }
+
+    /// Watches the serie named `show` up to an episode, for events of other programs. Returns an
+    /// http-like status code and a message
+    fn watch_until(&self, show: &str, season: usize, episode: usize) -> (u16, String) {
+        let watch_until = |serie: &mut Serie| match serie.watch_until(season, episode) {
+            WatchUntil::Watched => {
+                serie.mark_watched();
+                Ok(true)
+            }
+            WatchUntil::AlreadyPast => Ok(false),
+            WatchUntil::NoSuchEpisode => Err(format!("\"{}\" has no S{season:02}E{episode:02}", serie.name)),
+        };
+        // media servers and file names don't always spell the show like the serie
+        let found = utils::series_dirs_reader(&self.directories)
+            .map_err(|e| (500, e.to_string()))
+            .and_then(|series| utils::find_serie(series, show).ok_or((404, format!("No serie matches \"{show}\""))))
+            .and_then(|(_, path)| self.modify_serie_at(&path, watch_until));
+        match found {
+            Ok((serie, true)) => (200, format!("{} {}", serie.name, serie.next_episode_flat())),
+            Ok((serie, false)) => (200, format!("\"{}\" is already past S{season:02}E{episode:02}", serie.name)),
+            Err((status, message)) => {
+                eprintln!("WARNING: {message}");
+                (status, message)
+            }
+        }
+    }
+
+    /// Answers a request to the http api with a status code and json
+    fn api_request(&self, request: ApiRequest) -> (u16, serde_json::Value) {
+        let result = match request {
+            ApiRequest::List(include) => {
+                let selection = Selection {
+                    include: include.unwrap_or(self.include()),
+                    ..self.selection()
+                };
+                return match utils::series_dirs_reader(&self.directories) {
+                    Ok(series) => {
+                        let series: Vec<_> = series
+                            .filter(|(serie, path)| selection.matches(serie, path))
+                            .map(|(serie, _)| api::serie_json(&serie))
+                            .collect();
+                        (200, series.into())
+                    }
+                    Err(e) => (500, serde_json::json!({"error": e.to_string()})),
+                };
+            }
+            ApiRequest::Get(name) => self.find_serie(&name).map(|(serie, _)| (serie, false)),
+            ApiRequest::Watch(name, count) => self.modify_serie(&name, |serie| {
+                serie.watch(count);
+                serie.mark_watched();
+                Ok(true)
+            }),
+            ApiRequest::Unwatch(name, count) => self.modify_serie(&name, |serie| {
+                serie.unwatch(count);
+                Ok(true)
+            }),
+            ApiRequest::Seek(name, episode) => self.modify_serie(&name, |serie| {
+                match serie.find_episode(&episode) {
+                    Some((season, episode)) if serie.seek(season, episode) => Ok(true),
+                    _ => Err(format!("\"{}\" has no episode {episode}", serie.name)),
                 }
+            }),
+            ApiRequest::Add { source, id } => self.add_from_source(&source, &id),
+        };
+        match result {
+            Ok((serie, _)) => (200, api::serie_json(&serie)),
+            Err((status, message)) => (status, serde_json::json!({"error": message})),
+        }
+    }
+
+    /// Adds the show `id` of an online `source`, or updates it if it already exists
+    fn add_from_source(&self, source: &str, id: &str) -> Result<(Serie, bool), (u16, String)> {
+        let serie = match source {
+            "episodate" => episodate::fetch_detail(id).map_err(|e| (502, e))?,
+            "wikipedia" => wikipedia::fetch_movie(id).map_err(|e| (502, e))?,
+            source => return Err((400, format!("Unknown source \"{source}\", expected \"episodate\" or \"wikipedia\""))),
+        };
+        let path = self.directory().join(serie.filename());
+        if !self.dry_run {
+            let _ = fs::create_dir_all(self.directory());
+        }
+        let _lock = self.try_lock(&path)?;
+        let serie = match Serie::from_file(&path) {
+            Some(mut old_serie) => {
+                old_serie.merge_serie(&serie);
+                old_serie
             }
-            serie.unwatch(count);
-            serie.print(&self.print_mode, Some(&path));
-            if !self.dry_run {
-                serie.write(path).expect("Write failed");
+            None => serie,
+        };
+        serie.print(self.print_mode(), Some(&path));
+        if !self.dry_run {
+            if let Err(e) = serie.write(&path) {
+                eprintln!("ERROR: Couldn't write {}. Produced the following error:\n{}", path.to_str().unwrap(), e);
+                return Err((500, e.to_string()));
+            }
+        }
+        Ok((serie, true))
+    }
+
+    fn scan_series(&self, dir: &Path) {
+        if !self.dry_run {
+            let _ = fs::create_dir_all(self.directory());
+        }
+        for serie in scan::scan(dir) {
+            let path = self.directory().join(serie.filename());
+            let _lock = self.lock(&path);
+            let serie = match Serie::from_file(&path) {
+                Some(mut old_serie) => {
+                    old_serie.merge_serie(&serie);
+                    for media in serie.media {
+                        if !old_serie.media.contains(&media) {
+                            old_serie.media.push(media);
+                        }
+                    }
+                    old_serie
+                }
+                None => serie,
+            };
+            serie.print(self.print_mode(), Some(&path));
+            self.write_serie(&serie, &path);
+        }
+    }
+
+    fn play_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, player: &str, force: bool) {
+        let Some(words) = utils::split_command(player) else {
+            exit_with_error(format!("The player \"{player}\" has an unclosed quote"));
+        };
+        let (program, args) = words.split_first().map_or(("mpv", &[][..]), |(program, args)| (program.as_str(), args));
+        let series = select_series(series, force, "play the next episode of", |serie| {
+            format!("Do you want to play {} of \"{}\" [Y/n] ", serie.next_episode_flat(), serie.name)
+        });
+        for (serie, path) in series {
+            let Some(next_episode) = serie.next_episode_str() else {
+                eprintln!("WARNING: \"{}\" is finished. Skipping it...", serie.name);
+                continue;
+            };
+            // relative media are relative to the serie file
+            let parent = path.parent().unwrap_or(Path::new(""));
+            let media: Vec<String> = serie
+                .media
+                .iter()
+                .map(|media| if media.contains("://") {
+                    media.clone()
+                } else {
+                    parent.join(media).to_str().unwrap().to_string()
+                })
+                .collect();
+            let found = match serie.kind() {
+                Kind::Movie => media::find_movie(&media, &serie.name),
+                _ => media::find_episode(&media, serie.next_season(), serie.next_episode()),
+            };
+            let Some(episode) = found else {
+                let what = match serie.kind() {
+                    Kind::Movie => "the movie".to_string(),
+                    _ => format!("{next_episode} of"),
+                };
+                eprintln!(
+                    "WARNING: Couldn't find {what} \"{}\" in its media. Add them as \"media: <directory or url>\" lines to {}",
+                    serie.name,
+                    path.to_str().unwrap()
+                );
+                continue;
+            };
+            if self.dry_run {
+                eprintln!("Playing {player} \"{episode}\" (dry-run)");
+                continue;
+            }
+            match process::Command::new(program).args(args).arg(&episode).status() {
+                Ok(status) if status.success() => {
+                    let Some((_lock, mut serie)) = self.lock_and_reload(&path) else {
+                        continue;
+                    };
+                    serie.watch(1);
+                    serie.mark_watched();
+                    serie.print(self.print_mode(), Some(&path));
+                    self.write_serie(&serie, &path);
+                }
+                Ok(status) => eprintln!(
+                    "INFO: {program} exited with {status}. Not watching {next_episode} of \"{}\"",
+                    serie.name
+                ),
+                Err(e) => exit_with_error(format!("Couldn't run {program}: {e}")),
             }
         }
     }
 
     #[inline(always)]
     fn delete_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>) {
-        for (mut serie, path) in series {
+        for (serie, path) in series {
             if !self.force {
                 let prompt = format!("Do you want to delete \"{}\" [Y/n] ", serie.name) + if self.dry_run {
                     "(dry-run) "
@@ -319,13 +1545,44 @@ impl Cli {
     }
 
     #[inline(always)]
-    fn list_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>) {
+    fn list_series(&self, series: impl Iterator<Item = (Serie, PathBuf)>, sort: Sort, reverse: bool) {
+        let mut series: Vec<_> = series.collect();
+        sort.sort(&mut series);
+        if reverse {
+            series.reverse();
+        }
         for (serie, path) in series {
-            serie.print(&self.print_mode, Some(&path));
+            if let Some(profile) = self.profile_of(&path) {
+                print!("{profile}: ");
+            }
+            serie.print(self.print_mode(), Some(&path));
         }
     }
 }
 
+/// Series to apply a change to. Unless forced, the user picks them from a menu, or, when stdin
+/// isn't a terminal, confirms each of them with a prompt. Callers force it when files are given,
+/// or with --force
+fn select_series<'a>(
+    series: impl Iterator<Item = (Serie, PathBuf)> + 'a,
+    force: bool,
+    title: &str,
+    prompt: impl Fn(&Serie) -> String + 'a,
+) -> Box<dyn Iterator<Item = (Serie, PathBuf)> + 'a> {
+    if force {
+        Box::new(series)
+    } else if io::stdin().is_terminal() {
+        Box::new(picker::pick(series.collect(), title).into_iter())
+    } else {
+        Box::new(series.filter(move |(serie, _)| yes_no_confirmation(prompt(serie))))
+    }
+}
+
+fn exit_with_error(e: impl std::fmt::Display) -> ! {
+    eprintln!("ERROR: {e}");
+    process::exit(1);
+}
+
 fn yes_no_confirmation(prompt: String) -> bool {
     eprint!("{}", prompt);
     io::stderr().flush().expect("Flushing stdout failed.");
@@ -334,8 +1591,51 @@ fn yes_no_confirmation(prompt: String) -> bool {
         .read_line(&mut input)
         .expect("Reading input failed");
     let input = input.trim().to_lowercase();
-    if input == "n" {
-        return false
+    input != "n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cli() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_hidden() {
+        let hidden = |args: &[&str]| Cli::try_parse_from(args).unwrap().hidden;
+        assert_eq!(hidden(&["bw", "ls"]), None);
+        assert_eq!(hidden(&["bw", "-H", "ls"]), Some(true));
+        assert_eq!(hidden(&["bw", "ls", "--hidden"]), Some(true));
+        assert_eq!(hidden(&["bw", "--hidden=false", "ls"]), Some(false));
+    }
+
+    #[test]
+    fn test_sort() {
+        let serie = |content: &str, name: &str| {
+            let mut serie: Serie = content.parse().unwrap();
+            serie.name = name.to_string();
+            (serie, PathBuf::from(format!("{name}.bw")))
+        };
+        let mut series = vec![
+            serie("last-watched: 2024-05-30T21:04:05Z\n5/10", "dark"),
+            serie("1/2", "Chernobyl"),
+            serie("last-watched: 2024-01-02T10:00:00Z\n0/30", "Arcane"),
+        ];
+        let names = |series: &[(Serie, PathBuf)]| series.iter().map(|(serie, _)| serie.name.clone()).collect::<Vec<_>>();
+        Sort::Name.sort(&mut series);
+        assert_eq!(names(&series), ["Arcane", "Chernobyl", "dark"]);
+        Sort::Progress.sort(&mut series);
+        assert_eq!(names(&series), ["Arcane", "Chernobyl", "dark"]);
+        Sort::Remaining.sort(&mut series);
+        assert_eq!(names(&series), ["Chernobyl", "dark", "Arcane"]);
+        Sort::LastWatched.sort(&mut series);
+        assert_eq!(names(&series), ["Chernobyl", "Arcane", "dark"]);
+        series[0].0.rating = Some(9);
+        series[1].0.rating = Some(7);
+        Sort::Rating.sort(&mut series);
+        assert_eq!(names(&series), ["dark", "Arcane", "Chernobyl"]);
     }
-    return true
 }
@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{cli::Include, serie::PrintMode, utils};
+
+/// Defaults for the command line options, overridden by the options of each invocation
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    pub directory: Option<PathBuf>,
+    pub print_mode: Option<PrintMode>,
+    pub include: Option<Include>,
+    pub hidden: Option<bool>,
+    pub lock_timeout: Option<u64>,
+    pub player: Option<String>,
+    pub mpv_threshold: Option<f64>,
+    pub api_token: Option<String>,
+    pub notify_command: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+/// A named directory of series, with its own default options
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Profile {
+    pub directory: PathBuf,
+    pub print_mode: Option<PrintMode>,
+    pub include: Option<Include>,
+    pub hidden: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read(io::Error),
+    Parse(toml::de::Error),
+    UnknownProfile(String),
+    UnknownKey(String),
+    InvalidValue(String, toml::de::Error),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(e) => write!(f, "{e}"),
+            ConfigError::Parse(e) => write!(f, "{e}"),
+            ConfigError::UnknownProfile(name) => write!(f, "Profile \"{name}\" is not defined"),
+            ConfigError::UnknownKey(key) => write!(f, "Key \"{key}\" is not set"),
+            ConfigError::InvalidValue(key, e) => write!(f, "Invalid value for \"{key}\": {e}"),
+        }
+    }
+}
+
+impl Config {
+    /// Reads the config at `path`. A missing file is an empty config
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(content) => content.parse(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::Read(e)),
+        }
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, ConfigError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))
+    }
+}
+
+/// The config file as a plain toml table, for `bw config` to get and set dotted keys
+/// (e.g. `print-mode` or `profiles.alice.directory`)
+#[derive(Debug, Default)]
+pub struct ConfigTable(toml::Table);
+
+impl ConfigTable {
+    pub fn read(path: &Path) -> Result<Self, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map(Self).map_err(ConfigError::Parse),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(ConfigError::Read(e)),
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        utils::write_atomic(path, self.to_string().as_bytes())
+    }
+
+    pub fn get(&self, key: &str) -> Result<&toml::Value, ConfigError> {
+        let mut keys = key.split('.');
+        let mut value = self.0.get(keys.next().unwrap_or_default());
+        for key in keys {
+            value = value.and_then(|value| value.get(key));
+        }
+        value.ok_or_else(|| ConfigError::UnknownKey(key.to_string()))
+    }
+
+    /// Sets `key` to `value`, which is read as a toml value when possible and as a string
+    /// otherwise. Fails if the resulting config is invalid
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), ConfigError> {
+        let value = toml::from_str::<toml::Table>(&format!("value = {value}"))
+            .ok()
+            .and_then(|mut table| table.remove("value"))
+            .filter(|value| !value.is_table())
+            .unwrap_or_else(|| toml::Value::String(value.to_string()));
+        let mut table = self.0.clone();
+        let (parents, last) = key.rsplit_once('.').map_or((None, key), |(p, l)| (Some(p), l));
+        let mut current = &mut table;
+        for parent in parents.into_iter().flat_map(|p| p.split('.')) {
+            current = current
+                .entry(parent)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        }
+        current.insert(last.to_string(), value);
+        toml::Value::Table(table.clone())
+            .try_into::<Config>()
+            .map_err(|e| ConfigError::InvalidValue(key.to_string(), e))?;
+        self.0 = table;
+        Ok(())
+    }
+
+    pub fn unset(&mut self, key: &str) -> Result<(), ConfigError> {
+        let (parents, last) = key.rsplit_once('.').map_or((None, key), |(p, l)| (Some(p), l));
+        let mut current = &mut self.0;
+        for parent in parents.into_iter().flat_map(|p| p.split('.')) {
+            current = current
+                .get_mut(parent)
+                .and_then(toml::Value::as_table_mut)
+                .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))?;
+        }
+        current
+            .remove(last)
+            .map(|_| ())
+            .ok_or_else(|| ConfigError::UnknownKey(key.to_string()))
+    }
+
+    /// All the leaf values, with their dotted keys
+    pub fn list(&self) -> Vec<(String, &toml::Value)> {
+        fn flatten<'a>(prefix: &str, table: &'a toml::Table, out: &mut Vec<(String, &'a toml::Value)>) {
+            for (key, value) in table {
+                let key = if prefix.is_empty() {
+                    key.to_string()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                match value {
+                    toml::Value::Table(table) => flatten(&key, table, out),
+                    value => out.push((key, value)),
+                }
+            }
+        }
+        let mut out = vec![];
+        flatten("", &self.0, &mut out);
+        out
+    }
+}
+
+impl fmt::Display for ConfigTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::str::FromStr for Config {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        toml::from_str(s).map_err(ConfigError::Parse)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        let config: Config = r#"
+[profiles.alice]
+directory = "/home/alice/series"
+print-mode = "extended"
+include = "all"
+
+[profiles.bob]
+directory = "/home/bob/series"
+hidden = true
+"#
+        .parse()
+        .unwrap();
+        let alice = config.profile("alice").unwrap();
+        assert_eq!(alice.directory, PathBuf::from("/home/alice/series"));
+        assert!(matches!(alice.print_mode, Some(PrintMode::Extended)));
+        assert!(matches!(alice.include, Some(Include::All)));
+        assert_eq!(config.profile("bob").unwrap().hidden, Some(true));
+        assert!(matches!(config.profile("carol"), Err(ConfigError::UnknownProfile(_))));
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config: Config = "directory = \"/tmp/series\"\ninclude = \"finished\"".parse().unwrap();
+        assert_eq!(config.directory, Some(PathBuf::from("/tmp/series")));
+        assert!(matches!(config.include, Some(Include::Finished)));
+        assert!(config.print_mode.is_none());
+    }
+
+    #[test]
+    fn test_table_set_get() {
+        let mut table = ConfigTable::default();
+        table.set("print-mode", "extended").unwrap();
+        table.set("hidden", "true").unwrap();
+        table.set("profiles.alice.directory", "/home/alice").unwrap();
+        assert_eq!(table.get("print-mode").unwrap().as_str(), Some("extended"));
+        assert_eq!(table.get("hidden").unwrap().as_bool(), Some(true));
+        assert_eq!(table.get("profiles.alice.directory").unwrap().as_str(), Some("/home/alice"));
+        let keys: Vec<String> = table.list().into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["hidden", "print-mode", "profiles.alice.directory"]);
+        let config: Config = table.to_string().parse().unwrap();
+        assert!(matches!(config.print_mode, Some(PrintMode::Extended)));
+    }
+
+    #[test]
+    fn test_table_invalid() {
+        let mut table = ConfigTable::default();
+        assert!(matches!(table.set("print-mode", "fancy"), Err(ConfigError::InvalidValue(..))));
+        assert!(matches!(table.set("colors", "true"), Err(ConfigError::InvalidValue(..))));
+        assert!(matches!(table.get("print-mode"), Err(ConfigError::UnknownKey(_))));
+        table.set("include", "all").unwrap();
+        table.unset("include").unwrap();
+        assert!(matches!(table.unset("include"), Err(ConfigError::UnknownKey(_))));
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let config = "[profiles.alice]\ndirectory = \"/tmp\"\ncolor = true".parse::<Config>();
+        assert!(matches!(config, Err(ConfigError::Parse(_))));
+    }
+}
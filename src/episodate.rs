@@ -35,6 +35,18 @@ pub struct TvShow {
     permalink: String,
 }
 
+impl TvShow {
+    #[inline]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[inline]
+    pub fn permalink(&self) -> &str {
+        &self.permalink
+    }
+}
+
 impl Display for TvShow {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.permalink)
@@ -55,24 +67,83 @@ pub fn search_write_to_stdout(query: &str) {
     }
 }
 
+pub fn search(query: &str) -> Vec<TvShow> {
+    let mut tv_shows = vec![];
+    if let Ok(main_response) = request_pages(query, None) {
+        let pages = main_response.pages;
+        tv_shows.extend(main_response.tv_shows);
+        for i in 2..pages + 1 {
+            if let Ok(response) = request_pages(query, Some(i)) {
+                tv_shows.extend(response.tv_shows);
+            }
+        }
+    }
+    tv_shows
+}
+
 pub fn request_detail(permalink: &str) -> Serie {
+    fetch_detail(permalink).unwrap_or_else(|e| panic!("{e}"))
+}
+
+/// Like [`request_detail`], for callers that can't panic when the request fails
+pub fn fetch_detail(permalink: &str) -> Result<Serie, String> {
+    fetch_detail_until(permalink, None)
+}
+
+/// Like [`fetch_detail`], counting only the episodes aired by `date` (like `2024-05-30`, in UTC)
+pub fn fetch_aired(permalink: &str, date: &str) -> Result<Serie, String> {
+    fetch_detail_until(permalink, Some(date))
+}
+
+fn fetch_detail_until(permalink: &str, date: Option<&str>) -> Result<Serie, String> {
     let target = format!("https://episodate.com/api/show-details?q={permalink}");
-    let response = Client::new().get(target).send().expect("Error sending get request");
-    let body = response.text().expect("Error reading the resonse text");
-    let mut details: Value = serde_json::from_str(body.as_str()).expect("Error converting json");
-    let details: TvShowDetails = serde_json::from_value(std::mem::take(&mut details["tvShow"])).expect("Error converting to show details");
-    let mut last_season = 0;
+    let response = Client::new().get(target).send().map_err(|e| format!("Error sending get request: {e}"))?;
+    let body = response.text().map_err(|e| format!("Error reading the resonse text: {e}"))?;
+    let mut details: Value = serde_json::from_str(body.as_str()).map_err(|e| format!("Error converting json: {e}"))?;
+    let details: TvShowDetails = serde_json::from_value(std::mem::take(&mut details["tvShow"]))
+        .map_err(|_| format!("No show with the permalink \"{permalink}\""))?;
+    serie_of(details, permalink, date)
+}
+
+/// The serie of the show `permalink`, counting only the episodes aired by `date`
+fn serie_of(details: TvShowDetails, permalink: &str, date: Option<&str>) -> Result<Serie, String> {
+    let (seasons, specials) = count_episodes(&details.episodes, date);
+    // a serie is written as its seasons, so one of only specials would be an empty file
+    if seasons.is_empty() {
+        return Err(format!("\"{}\" has no episodes out of the specials", details.name));
+    }
+    let mut serie = Serie::new(seasons, details.name);
+    serie.specials = specials;
+    serie.poster = details.image_path;
+    serie.source = Some(format!("episodate:{permalink}"));
+    Ok(serie)
+}
+
+/// Episodes of each season, and the specials (season 0) if there are any, counting only the ones
+/// aired by `date`
+fn count_episodes(episodes: &[EpisodeData], date: Option<&str>) -> (Vec<Season>, Option<Season>) {
     let mut seasons: Vec<Season> = vec![];
-    for episode in details.episodes {
-        if last_season != episode.season {
-            last_season = episode.season;
-            while seasons.len() < last_season {
-                seasons.push(Season::new(0));
+    let mut specials: Option<Season> = None;
+    for episode in episodes {
+        if let Some(date) = date {
+            // air dates are like "2013-08-11 01:00:00"
+            let aired = episode.air_date.as_deref().and_then(|aired| aired.get(..date.len()));
+            if aired.is_none_or(|aired| aired > date) {
+                continue;
             }
         }
-        seasons[last_season - 1].episodes += 1;
+        let season = match episode.season.checked_sub(1) {
+            Some(index) => {
+                while seasons.len() <= index {
+                    seasons.push(Season::new(0));
+                }
+                &mut seasons[index]
+            }
+            None => specials.get_or_insert_with(Season::default),
+        };
+        season.episodes += 1;
     }
-    Serie::new(seasons, details.name)
+    (seasons, specials)
 }
 
 fn request_pages(query: &str, page: Option<usize>) -> PageResult<Response> {
@@ -90,17 +161,45 @@ fn request_pages(query: &str, page: Option<usize>) -> PageResult<Response> {
 struct TvShowDetails {
     episodes: Vec<EpisodeData>,
     name: String,
+    image_path: Option<String>,
 }
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct EpisodeData {
     season: usize,
     episode: usize,
     name: String,
+    air_date: Option<String>,
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+
+    #[test]
+    fn test_count_episodes() {
+        let episodes: Vec<EpisodeData> = serde_json::from_str(r#"[
+            {"season": 0, "episode": 1, "name": "Special", "air_date": "2013-01-01 01:00:00"},
+            {"season": 1, "episode": 1, "name": "Pilot", "air_date": "2013-02-01 01:00:00"},
+            {"season": 1, "episode": 2, "name": "Second", "air_date": "2013-02-08 01:00:00"},
+            {"season": 0, "episode": 2, "name": "Another special", "air_date": "2013-03-01 01:00:00"},
+            {"season": 3, "episode": 1, "name": "Skipped a season", "air_date": null}
+        ]"#).unwrap();
+        let (seasons, specials) = count_episodes(&episodes, None);
+        assert_eq!(seasons, [Season::new(2), Season::new(0), Season::new(1)]);
+        assert_eq!(specials, Some(Season::new(2)));
+        let (seasons, specials) = count_episodes(&episodes, Some("2013-02-01"));
+        assert_eq!(seasons, [Season::new(1)]);
+        assert_eq!(specials, Some(Season::new(1)));
+    }
+
+    #[test]
+    fn test_serie_of_only_specials() {
+        let details: TvShowDetails = serde_json::from_str(r#"{"name": "Specials", "image_path": null, "episodes": [
+            {"season": 0, "episode": 1, "name": "Special", "air_date": "2013-01-01 01:00:00"}
+        ]}"#).unwrap();
+        assert!(serie_of(details, "specials", None).is_err());
+    }
+
     #[test]
     fn test_page_detail_breaking_bad() {
         let serie = request_detail("breaking-bad");
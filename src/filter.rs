@@ -0,0 +1,383 @@
+use std::{error::Error, fmt, path::Path, str::FromStr};
+
+use crate::{cli::Include, serie::Serie, utils};
+
+/// Numbers of a serie that can be compared, like `remaining<5`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    /// Episodes left to watch
+    Remaining,
+    Watched,
+    Episodes,
+    /// Percentage of watched episodes
+    Progress,
+    Seasons,
+    /// Season of the next episode
+    Season,
+    /// From 1 to 10, 0 when unrated
+    Rating,
+}
+
+impl FromStr for Field {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "remaining" => Ok(Field::Remaining),
+            "watched" => Ok(Field::Watched),
+            "episodes" => Ok(Field::Episodes),
+            "progress" => Ok(Field::Progress),
+            "seasons" => Ok(Field::Seasons),
+            "season" => Ok(Field::Season),
+            "rating" => Ok(Field::Rating),
+            field => Err(FilterError(format!("Unknown field \"{field}\""))),
+        }
+    }
+}
+
+impl Field {
+    fn value(&self, serie: &Serie) -> f64 {
+        match self {
+            Field::Remaining => serie.total_episodes().saturating_sub(serie.total_watched()) as f64,
+            Field::Watched => serie.total_watched() as f64,
+            Field::Episodes => serie.total_episodes() as f64,
+            Field::Progress => {
+                let percentage = serie.watched_percentage() as f64;
+                if percentage.is_nan() { 0. } else { percentage }
+            }
+            Field::Seasons => serie.seasons().len() as f64,
+            Field::Season => serie.next_season() as f64,
+            Field::Rating => serie.rating.unwrap_or(0) as f64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Op {
+    fn compare(&self, a: f64, b: f64) -> bool {
+        match self {
+            Op::Less => a < b,
+            Op::LessEqual => a <= b,
+            Op::Greater => a > b,
+            Op::GreaterEqual => a >= b,
+            Op::Equal => a == b,
+            Op::NotEqual => a != b,
+        }
+    }
+}
+
+/// Whether a serie is something, like `finished`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flag {
+    Finished,
+    /// Has any watched episode
+    Started,
+    /// Its file is dot-prefixed
+    Hidden,
+}
+
+/// A filter over series, like `remaining<5 && tag:anime && !state:dropped`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Or(Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, f64),
+    /// `key:value`, matching the metadata lines of the serie (`name:` matches part of the name)
+    Metadata(String, String),
+    Flag(Flag),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FilterError(String);
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for FilterError {}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Not,
+    And,
+    Or,
+    Colon,
+    Op(Op),
+    Word(String),
+}
+
+fn tokenize(s: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            ':' => Token::Colon,
+            '&' | '|' => {
+                if chars.next() != Some(c) {
+                    return Err(FilterError(format!("Expected \"{c}{c}\"")));
+                }
+                if c == '&' { Token::And } else { Token::Or }
+            }
+            '!' | '<' | '>' | '=' => {
+                let equal = chars.next_if_eq(&'=').is_some();
+                match (c, equal) {
+                    ('!', false) => Token::Not,
+                    ('!', true) => Token::Op(Op::NotEqual),
+                    ('<', false) => Token::Op(Op::Less),
+                    ('<', true) => Token::Op(Op::LessEqual),
+                    ('>', false) => Token::Op(Op::Greater),
+                    ('>', true) => Token::Op(Op::GreaterEqual),
+                    _ => Token::Op(Op::Equal),
+                }
+            }
+            '"' | '\'' => {
+                let mut word = String::new();
+                loop {
+                    match chars.next() {
+                        Some(end) if end == c => break,
+                        Some(char) => word.push(char),
+                        None => return Err(FilterError(format!("Unclosed {c}"))),
+                    }
+                }
+                Token::Word(word)
+            }
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()!&|<>=:\"'".contains(*c)) {
+                    word.push(c);
+                }
+                Token::Word(word)
+            }
+        };
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn next_if(&mut self, token: &Token) -> bool {
+        let matches = self.tokens.get(self.position) == Some(token);
+        if matches {
+            self.position += 1;
+        }
+        matches
+    }
+
+    fn word(&mut self, after: &str) -> Result<String, FilterError> {
+        match self.next() {
+            Some(Token::Word(word)) => Ok(word),
+            _ => Err(FilterError(format!("Expected a value after \"{after}\""))),
+        }
+    }
+
+    fn or(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.and()?;
+        while self.next_if(&Token::Or) {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<Expr, FilterError> {
+        let mut expr = self.unary()?;
+        while self.next_if(&Token::And) {
+            expr = Expr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, FilterError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let expr = self.or()?;
+                if !self.next_if(&Token::Close) {
+                    return Err(FilterError("Expected \")\"".to_string()));
+                }
+                Ok(expr)
+            }
+            Some(Token::Word(word)) => self.atom(word),
+            _ => Err(FilterError("Expected a condition".to_string())),
+        }
+    }
+
+    fn atom(&mut self, word: String) -> Result<Expr, FilterError> {
+        if self.next_if(&Token::Colon) {
+            let mut value = self.word(&format!("{word}:"))?;
+            // values can have colons too, like source:episodate:dark
+            while self.next_if(&Token::Colon) {
+                value = format!("{value}:{}", self.word(&value)?);
+            }
+            return Ok(Expr::Metadata(word.to_lowercase(), value));
+        }
+        if let Some(Token::Op(op)) = self.tokens.get(self.position).cloned() {
+            self.position += 1;
+            let field: Field = word.parse()?;
+            let number = self.word(&word)?;
+            let number = number
+                .parse()
+                .map_err(|_| FilterError(format!("\"{number}\" is not a number")))?;
+            return Ok(Expr::Compare(field, op, number));
+        }
+        match word.as_str() {
+            "finished" => Ok(Expr::Flag(Flag::Finished)),
+            "started" => Ok(Expr::Flag(Flag::Started)),
+            "hidden" => Ok(Expr::Flag(Flag::Hidden)),
+            word => Err(FilterError(format!("Unknown condition \"{word}\""))),
+        }
+    }
+}
+
+impl FromStr for Expr {
+    type Err = FilterError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            tokens: tokenize(s)?,
+            position: 0,
+        };
+        let expr = parser.or()?;
+        if parser.position < parser.tokens.len() {
+            return Err(FilterError(format!("Unexpected {:?}", parser.tokens[parser.position])));
+        }
+        Ok(expr)
+    }
+}
+
+impl Expr {
+    pub fn matches(&self, serie: &Serie, path: &Path) -> bool {
+        match self {
+            Expr::Or(a, b) => a.matches(serie, path) || b.matches(serie, path),
+            Expr::And(a, b) => a.matches(serie, path) && b.matches(serie, path),
+            Expr::Not(expr) => !expr.matches(serie, path),
+            Expr::Compare(field, op, number) => op.compare(field.value(serie), *number),
+            Expr::Metadata(key, value) if key == "name" => serie.matches(value),
+            Expr::Metadata(key, value) => serie
+                .metadata(key)
+                .iter()
+                .any(|found| found.eq_ignore_ascii_case(value)),
+            Expr::Flag(Flag::Finished) => serie.is_finished(),
+            Expr::Flag(Flag::Started) => serie.total_watched() > 0,
+            Expr::Flag(Flag::Hidden) => utils::is_hidden(path),
+        }
+    }
+}
+
+/// What selects the series a command acts on: `--include`, `--hidden`, `--where` and `--tag`
+/// together
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub include: Include,
+    pub hidden: bool,
+    pub filter: Option<Expr>,
+    /// Tags a serie needs all of
+    pub tags: Vec<String>,
+}
+
+impl Selection {
+    pub fn matches(&self, serie: &Serie, path: &Path) -> bool {
+        self.include.matches(serie)
+            && (self.hidden || !utils::is_hidden(path))
+            && self.filter.as_ref().is_none_or(|filter| filter.matches(serie, path))
+            && self.tags.iter().all(|tag| serie.has_tag(tag))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    fn matches(expr: &str, content: &str) -> bool {
+        let serie = serie(content, "Breaking Bad");
+        expr.parse::<Expr>().unwrap().matches(&serie, Path::new("/series/Breaking Bad.bw"))
+    }
+
+    #[test]
+    fn test_parse() {
+        let expr: Expr = "remaining<5 && (tag:anime || !finished)".parse().unwrap();
+        assert_eq!(
+            expr,
+            Expr::And(
+                Box::new(Expr::Compare(Field::Remaining, Op::Less, 5.)),
+                Box::new(Expr::Or(
+                    Box::new(Expr::Metadata("tag".to_string(), "anime".to_string())),
+                    Box::new(Expr::Not(Box::new(Expr::Flag(Flag::Finished)))),
+                )),
+            )
+        );
+        assert_eq!("name:\"breaking bad\"".parse(), Ok(Expr::Metadata("name".to_string(), "breaking bad".to_string())));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!("remaining<".parse::<Expr>().is_err());
+        assert!("length>5".parse::<Expr>().is_err());
+        assert!("finished &".parse::<Expr>().is_err());
+        assert!("(finished".parse::<Expr>().is_err());
+        assert!("finished started".parse::<Expr>().is_err());
+        assert!("sometimes".parse::<Expr>().is_err());
+    }
+
+    #[test]
+    fn test_matches() {
+        let content = "tags: drama\nsource: episodate:breaking-bad\nrating: 9\n7/7\n10/13";
+        assert!(matches("remaining<5 && remaining>=3", content));
+        assert!(matches("progress>=85 && seasons==2 && season=2", content));
+        assert!(matches("tag:drama && !finished && started", content));
+        assert!(matches("name:breaking && source:episodate:breaking-bad", content));
+        assert!(!matches("hidden || tag:anime", content));
+        assert!(matches("finished || episodes!=19", content));
+        assert!(matches("state:watching", content));
+        assert!(matches("rating>=8 && !(rating==0)", content));
+        // more watched than there are episodes
+        assert!(matches("remaining==0", "12/10"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let serie: Serie = "tags: anime\n7/7\n10/13".parse().unwrap();
+        let selection = Selection {
+            include: Include::NoFinished,
+            hidden: false,
+            filter: Some("remaining<5 && tag:anime".parse().unwrap()),
+            tags: vec!["Anime".to_string()],
+        };
+        assert!(selection.matches(&serie, Path::new("/series/Dark.bw")));
+        assert!(!selection.matches(&serie, Path::new("/series/.Dark.bw")));
+        let finished = Selection { include: Include::Finished, ..selection.clone() };
+        assert!(!finished.matches(&serie, Path::new("/series/Dark.bw")));
+        let paused: Serie = "tags: anime\nstate: paused\n7/7\n10/13".parse().unwrap();
+        assert!(!Selection { filter: None, ..selection.clone() }.matches(&paused, Path::new("/series/Dark.bw")));
+        let tagged = Selection { tags: vec!["anime".to_string(), "rewatch".to_string()], ..selection };
+        assert!(!tagged.matches(&serie, Path::new("/series/Dark.bw")));
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt::Write;
+
+use crate::{cli::Include, serie::Serie};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em auto; max-width: 72em; padding: 0 1em; background: #111; color: #ddd; }
+h2 { border-bottom: 1px solid #444; padding-bottom: .3em; }
+.series { display: grid; grid-template-columns: repeat(auto-fill, minmax(20em, 1fr)); gap: 1em; }
+.serie { background: #1c1c1c; border-radius: .5em; padding: 1em; display: flex; gap: 1em; }
+.serie img { width: 6em; height: 9em; object-fit: cover; border-radius: .3em; }
+.serie h3 { margin: 0 0 .3em; }
+.info { flex: 1; min-width: 0; }
+.progress { background: #333; border-radius: .3em; height: .6em; margin: .4em 0; }
+.progress div { background: #4a9; border-radius: .3em; height: 100%; }
+.next { color: #aaa; font-size: .9em; }
+.season { display: flex; flex-wrap: wrap; gap: 2px; margin-top: 3px; }
+.season span { width: .7em; height: .7em; background: #333; }
+.season span.watched { background: #4a9; }
+";
+
+/// Escapes text to put in html content or attribute values
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn render_serie(html: &mut String, serie: &Serie) {
+    let percentage = serie.watched_percentage();
+    let percentage = if percentage.is_nan() { 0. } else { percentage };
+    let name = escape(&serie.name);
+    html.push_str("<article class=\"serie\">");
+    if let Some(poster) = &serie.poster {
+        let _ = write!(html, "<img src=\"{}\" alt=\"{name}\" loading=\"lazy\">", escape(poster));
+    }
+    let _ = write!(
+        html,
+        "<div class=\"info\"><h3>{name}</h3><div class=\"progress\" title=\"{}/{} episodes\"><div style=\"width: {percentage:.1}%\"></div></div>",
+        serie.total_watched(),
+        serie.total_episodes()
+    );
+    let next = match serie.next_episode_str() {
+        Some(_) => format!("next: {}", serie.next_episode_rewatch()),
+        None => "finished".to_string(),
+    };
+    let _ = write!(html, "<div class=\"next\">{next} · {percentage:.0}%</div>");
+    for (season, number) in serie.seasons().iter().zip(1..) {
+        let _ = write!(html, "<div class=\"season\" title=\"Season {number}: {}\">", escape(&season.to_string()));
+        for episode in 1..=season.episodes {
+            let class = if episode <= season.watched { " class=\"watched\"" } else { "" };
+            let _ = write!(html, "<span{class}></span>");
+        }
+        html.push_str("</div>");
+    }
+    html.push_str("</div></article>\n");
+}
+
+/// A standalone page of `series`, in a section for each state
+pub fn render(series: &[Serie]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n<title>bingewatcher</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>bingewatcher</h1>\n"
+    );
+    let groups = [
+        (Include::Watching, "Watching"),
+        (Include::PlanToWatch, "Plan to watch"),
+        (Include::Paused, "Paused"),
+        (Include::Dropped, "Dropped"),
+        (Include::Finished, "Finished"),
+    ];
+    for (include, title) in groups {
+        let mut group: Vec<&Serie> = series.iter().filter(|serie| include.matches(serie)).collect();
+        if group.is_empty() {
+            continue;
+        }
+        group.sort_by_key(|serie| serie.name.to_lowercase());
+        let _ = write!(html, "<h2>{title} ({})</h2>\n<section class=\"series\">\n", group.len());
+        for serie in group {
+            render_serie(&mut html, serie);
+        }
+        html.push_str("</section>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("Tom & Jerry <\"1940\">"), "Tom &amp; Jerry &lt;&quot;1940&quot;&gt;");
+    }
+
+    #[test]
+    fn test_render() {
+        let series = [
+            serie("poster: https://example.com/dark.jpg\n8/8\n1/3", "Dark"),
+            serie("3/3", "Chernobyl"),
+            serie("0/2", "Arcane"),
+            serie("state: paused\n1/10", "Severance"),
+        ];
+        let html = render(&series);
+        let watching = html.find("Watching (2)").unwrap();
+        let finished = html.find("Finished (1)").unwrap();
+        assert!(watching < html.find("Arcane").unwrap());
+        assert!(html.find("Arcane").unwrap() < html.find("<h3>Dark").unwrap());
+        assert!(finished < html.find("Chernobyl").unwrap());
+        assert!(html.find("Paused (1)").unwrap() < html.find("Severance").unwrap());
+        assert!(html.contains("<img src=\"https://example.com/dark.jpg\""));
+        assert!(html.contains("next: S02E02"));
+        assert_eq!(html.matches("<span class=\"watched\"></span>").count(), 8 + 1 + 3 + 1);
+    }
+}
@@ -1,4 +1,21 @@
 pub mod utils;
+pub mod api;
 pub mod cli;
+pub mod config;
 pub mod episodate;
+pub mod filter;
+pub mod html;
+pub mod library;
+pub mod lock;
+pub mod media;
+// mpv talks over a unix socket
+#[cfg(unix)]
+pub mod mpv;
+pub mod notify;
+pub mod picker;
+pub mod scan;
 pub mod serie;
+pub mod status;
+pub mod tui;
+pub mod webhook;
+pub mod wikipedia;
@@ -0,0 +1,161 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+use crate::{serie::Serie, utils};
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum Format {
+    #[value(alias = "j")]
+    Json,
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum OnConflict {
+    /// Keep the existing file untouched
+    #[value(alias = "s")]
+    Skip,
+    /// Merge the imported serie into the existing file (new seasons and episodes)
+    #[value(alias = "m")]
+    Merge,
+    /// Overwrite the existing file with the imported serie
+    #[value(alias = "r")]
+    Replace,
+}
+
+/// A snapshot of a set of series, used to move watch state between machines
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Library {
+    pub series: Vec<Serie>,
+}
+
+impl Library {
+    pub fn from_series(series: impl Iterator<Item = Serie>) -> Self {
+        Self {
+            series: series.collect(),
+        }
+    }
+
+    pub fn write<W: Write>(&self, writer: W, format: &Format) -> io::Result<()> {
+        match format {
+            Format::Json => serde_json::to_writer_pretty(writer, self)?,
+        }
+        Ok(())
+    }
+
+    pub fn read(path: &Path, format: &Format) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        Self::parse(&content, format)
+    }
+
+    /// Why `serie` can't be written to a file of its own, if it can't. Snapshots may come from
+    /// anywhere, so names can't be trusted to stay in the series directory, nor values to fit in
+    /// their line
+    pub fn invalid(serie: &Serie) -> Option<&'static str> {
+        let name = &serie.name;
+        let seasons = || serie.seasons().iter().chain(&serie.specials);
+        let mut values = serie
+            .media
+            .iter()
+            .chain(&serie.tags)
+            .chain(&serie.notes)
+            .chain(&serie.poster)
+            .chain(&serie.source)
+            .chain(&serie.last_watched)
+            .chain(&serie.watched_through)
+            .chain(seasons().flat_map(|season| &season.notes))
+            .chain(serie.extra().iter().map(|(_, value)| value));
+        if !utils::is_file_name(name) {
+            Some("its name can't be a file name")
+        } else if serie.seasons().is_empty() {
+            Some("it has no seasons")
+        } else if seasons().any(|season| season.watched > season.episodes) {
+            Some("it has a season with more watched episodes than episodes")
+        } else if values.any(|value| value.contains(['\n', '\r'])) {
+            Some("it has line breaks in its values")
+        } else if serie.tags.iter().any(|tag| tag.contains(',')) {
+            Some("it has commas in its tags")
+        } else if serie.extra().iter().any(|(key, _)| {
+            key.is_empty() || key.starts_with(|c: char| c.is_ascii_digit()) || key.contains([':', '\n', '\r'])
+        }) {
+            Some("it has a key that can't be written")
+        } else {
+            None
+        }
+    }
+
+    pub fn parse(content: &str, format: &Format) -> io::Result<Self> {
+        let mut library: Library = match format {
+            Format::Json => serde_json::from_str(content)?,
+        };
+        for serie in library.series.iter_mut() {
+            serie.update_current_season();
+        }
+        Ok(library)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    #[test]
+    fn test_round_trip() {
+        let serie = serie("10/20\n0/20", "Breaking Bad");
+        let library = Library::from_series([serie].into_iter());
+        let mut buf = Vec::new();
+        library.write(&mut buf, &Format::Json).unwrap();
+        let imported = Library::parse(&String::from_utf8(buf).unwrap(), &Format::Json).unwrap();
+        assert_eq!(imported.series, library.series);
+        assert_eq!(imported.series[0].next_episode_str().unwrap(), "S01E11");
+    }
+
+    #[test]
+    fn test_invalid() {
+        let content = r#"{"series":[
+            {"name":"Dark","seasons":[{"watched":1,"episodes":10}]},
+            {"name":"../Dark","seasons":[{"watched":1,"episodes":10}]},
+            {"name":"/etc/Dark","seasons":[{"watched":1,"episodes":10}]},
+            {"name":"..","seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Lost","seasons":[]}
+        ]}"#;
+        let library = Library::parse(content, &Format::Json).unwrap();
+        let invalid: Vec<_> = library.series.iter().map(Library::invalid).collect();
+        assert_eq!(invalid[0], None);
+        assert!(invalid[1..4].iter().all(|reason| *reason == Some("its name can't be a file name")));
+        assert_eq!(invalid[4], Some("it has no seasons"));
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        let content = r#"{"series":[
+            {"name":"Lost","notes":"a\nb","seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Fargo","tags":["x\ny"],"seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Dark","seasons":[{"watched":1,"episodes":10,"notes":"a\rb"}]},
+            {"name":"Dark","extra":[["mood","a\nb"]],"seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Dark","tags":["x,y"],"seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Dark","extra":[["10","x"]],"seasons":[{"watched":1,"episodes":10}]},
+            {"name":"Dark","seasons":[{"watched":12,"episodes":10}]},
+            {"name":"Dark","specials":{"watched":3,"episodes":2},"seasons":[{"watched":1,"episodes":10}]}
+        ]}"#;
+        let library = Library::parse(content, &Format::Json).unwrap();
+        let invalid: Vec<_> = library.series.iter().map(Library::invalid).collect();
+        assert!(invalid[..4].iter().all(|reason| *reason == Some("it has line breaks in its values")));
+        assert_eq!(invalid[4], Some("it has commas in its tags"));
+        assert_eq!(invalid[5], Some("it has a key that can't be written"));
+        assert!(invalid[6..].iter().all(|reason| *reason == Some("it has a season with more watched episodes than episodes")));
+    }
+
+    #[test]
+    fn test_parse_finished() {
+        let content = r#"{"series":[{"name":"Dark","seasons":[{"watched":10,"episodes":10}]}]}"#;
+        let library = Library::parse(content, &Format::Json).unwrap();
+        assert!(library.series[0].is_finished());
+        assert_eq!(library.series[0].next_episode_str(), None);
+    }
+}
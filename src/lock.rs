@@ -0,0 +1,115 @@
+use std::{
+    fmt,
+    fs::{File, OpenOptions, TryLockError},
+    io,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+
+const LOCK_FILE: &str = ".lock";
+const RETRY_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An advisory lock on a series directory, released when dropped
+#[derive(Debug)]
+pub struct DirLock {
+    file: File,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    Io(io::Error),
+    TimedOut(PathBuf),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Io(e) => write!(f, "{e}"),
+            LockError::TimedOut(path) => write!(
+                f,
+                "Another bw is still holding the lock {}. Try again when it's done",
+                path.to_str().unwrap()
+            ),
+        }
+    }
+}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        LockError::Io(e)
+    }
+}
+
+/// The lock file guarding the directory of the serie file at `path`
+#[inline]
+pub fn lock_path(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.join(LOCK_FILE),
+        _ => PathBuf::from(LOCK_FILE),
+    }
+}
+
+impl DirLock {
+    /// Locks the directory of the serie file at `path`, waiting up to `timeout` for other bw
+    /// invocations to release it. `on_wait` is called once, if the lock is held by someone else
+    pub fn acquire(path: &Path, timeout: Duration, on_wait: impl FnOnce(&Path)) -> Result<Self, LockError> {
+        let lock_path = lock_path(path);
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)?;
+        let start = Instant::now();
+        let mut on_wait = Some(on_wait);
+        loop {
+            match file.try_lock() {
+                Ok(()) => return Ok(Self { file }),
+                Err(TryLockError::Error(e)) => return Err(e.into()),
+                Err(TryLockError::WouldBlock) if start.elapsed() >= timeout => {
+                    return Err(LockError::TimedOut(lock_path))
+                }
+                Err(TryLockError::WouldBlock) => {
+                    if let Some(on_wait) = on_wait.take() {
+                        on_wait(&lock_path);
+                    }
+                    thread::sleep(RETRY_INTERVAL);
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DirLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_lock_timeout() {
+        let dir = temp_dir("lock");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("Dark.bw");
+        let lock = DirLock::acquire(&path, Duration::ZERO, |_| {}).unwrap();
+        let mut waited = false;
+        let result = DirLock::acquire(&path, Duration::from_millis(200), |_| waited = true);
+        assert!(matches!(result, Err(LockError::TimedOut(_))));
+        assert!(waited);
+        drop(lock);
+        assert!(DirLock::acquire(&path, Duration::ZERO, |_| {}).is_ok());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_lock_path() {
+        assert_eq!(lock_path(Path::new("Dark.bw")), PathBuf::from(".lock"));
+        assert_eq!(lock_path(Path::new("/tmp/series/Dark.bw")), PathBuf::from("/tmp/series/.lock"));
+    }
+}
@@ -4,9 +4,7 @@ use std::{
     io
 };
 use clap::Parser;
-use bw::{
-    cli::{Cli, Commands::*, OnlineCommands::*},
-};
+use bw::cli::Cli;
 
 //}}}
 
@@ -0,0 +1,204 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+const VIDEO_EXTENSIONS: &[&str] = &[
+    "mkv", "mp4", "avi", "webm", "mov", "m4v", "ts", "wmv", "flv", "mpg", "mpeg",
+];
+
+#[inline(always)]
+fn digits_at(bytes: &[u8], start: usize) -> &[u8] {
+    let len = bytes[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    &bytes[start..start + len]
+}
+
+/// The number of `digits`, or None when it doesn't fit
+#[inline(always)]
+fn to_number(digits: &[u8]) -> Option<usize> {
+    digits
+        .iter()
+        .try_fold(0usize, |n, d| n.checked_mul(10)?.checked_add((d - b'0') as usize))
+}
+
+/// Finds the season and episode in a file name, like `Show.S02E05.mkv`, `show s2e5.mp4` or
+/// `Show 2x05.avi`
+#[inline]
+pub fn episode_of(name: &str) -> Option<(usize, usize)> {
+    find_episode_pattern(name).map(|(season, episode, _)| (season, episode))
+}
+
+/// Like [`episode_of`], also returning where the pattern starts in `name`
+pub fn find_episode_pattern(name: &str) -> Option<(usize, usize, usize)> {
+    let lower = name.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let boundary = |i: usize| i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+    for i in 0..bytes.len() {
+        if !boundary(i) {
+            continue;
+        }
+        // S02E05
+        if bytes[i] == b's' {
+            let season = digits_at(bytes, i + 1);
+            let e = i + 1 + season.len();
+            if !season.is_empty() && bytes.get(e) == Some(&b'e') {
+                let episode = digits_at(bytes, e + 1);
+                if !episode.is_empty() {
+                    if let (Some(season), Some(episode)) = (to_number(season), to_number(episode)) {
+                        return Some((season, episode, i));
+                    }
+                }
+            }
+        }
+        // 2x05, but not resolutions like 1920x1080
+        let season = digits_at(bytes, i);
+        let x = i + season.len();
+        if (1..=2).contains(&season.len()) && bytes.get(x) == Some(&b'x') {
+            let episode = digits_at(bytes, x + 1);
+            let end = x + 1 + episode.len();
+            if (2..=3).contains(&episode.len()) && bytes.get(end).is_none_or(|b| !b.is_ascii_alphanumeric()) {
+                if let (Some(season), Some(episode)) = (to_number(season), to_number(episode)) {
+                    return Some((season, episode, i));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[inline]
+pub fn is_video(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// All the video files under `dir`, sorted
+pub fn videos_in(dir: &Path) -> Vec<PathBuf> {
+    let mut videos = vec![];
+    let mut dirs = vec![dir.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            if path.is_dir() {
+                dirs.push(path);
+            } else if is_video(&path) {
+                videos.push(path);
+            }
+        }
+    }
+    videos.sort();
+    videos
+}
+
+/// Finds `episode` of `season` in the media of a serie. Each media is either a url, a video
+/// file, or a directory that is searched recursively
+pub fn find_episode(media: &[String], season: usize, episode: usize) -> Option<String> {
+    let matches = |name: &str| episode_of(name) == Some((season, episode));
+    for media in media {
+        let path = Path::new(media);
+        if media.contains("://") {
+            let name = media.rsplit('/').next().unwrap_or(media);
+            if matches(name) {
+                return Some(media.clone());
+            }
+        } else if path.is_dir() {
+            let found = videos_in(path).into_iter().find(|video| {
+                video.file_name().and_then(|name| name.to_str()).is_some_and(matches)
+            });
+            if let Some(video) = found {
+                return video.to_str().map(str::to_string);
+            }
+        } else if path.file_name().and_then(|name| name.to_str()).is_some_and(matches) {
+            return Some(media.clone());
+        }
+    }
+    None
+}
+
+/// Finds a movie, which has no episode to look for, in the media of a serie. A url or video file
+/// is the movie itself, and in a directory it's the only video, or the one named like `title`
+/// (ignoring case and punctuation)
+pub fn find_movie(media: &[String], title: &str) -> Option<String> {
+    let simplify = |s: &str| s.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect::<String>();
+    let title = simplify(title);
+    for media in media {
+        let path = Path::new(media);
+        if media.contains("://") || (path.is_file() && is_video(path)) {
+            return Some(media.clone());
+        }
+        let mut videos = videos_in(path);
+        let found = if videos.len() == 1 {
+            videos.pop()
+        } else {
+            videos.into_iter().find(|video| {
+                video.file_stem().and_then(|name| name.to_str()).is_some_and(|name| simplify(name).contains(&title))
+            })
+        };
+        if let Some(video) = found {
+            return video.to_str().map(str::to_string);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::temp_dir;
+
+    #[test]
+    fn test_episode_of() {
+        assert_eq!(episode_of("Breaking.Bad.S02E05.720p.mkv"), Some((2, 5)));
+        assert_eq!(episode_of("breaking bad s2e5.mp4"), Some((2, 5)));
+        assert_eq!(episode_of("Breaking Bad - 2x05 - Breakage.avi"), Some((2, 5)));
+        assert_eq!(episode_of("Breaking.Bad.1920x1080.mkv"), None);
+        assert_eq!(episode_of("Sherlock.mkv"), None);
+        assert_eq!(episode_of("Dexter.S01E01E02.mkv"), Some((1, 1)));
+        assert_eq!(episode_of("Dexter.S99999999999999999999999E01.mkv"), None);
+        assert_eq!(episode_of("Dexter S99999999999999999999999E01 1x02.mkv"), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_find_episode_urls() {
+        let media = vec![
+            "https://example.com/dark/Dark.S01E01.mkv".to_string(),
+            "https://example.com/dark/Dark.S01E02.mkv".to_string(),
+        ];
+        assert_eq!(find_episode(&media, 1, 2).as_deref(), Some("https://example.com/dark/Dark.S01E02.mkv"));
+        assert_eq!(find_episode(&media, 2, 1), None);
+    }
+
+    #[test]
+    fn test_find_episode_dir() {
+        let dir = temp_dir("media");
+        let season = dir.join("Season 02");
+        fs::create_dir_all(&season).unwrap();
+        fs::write(season.join("Dark.S02E03.srt"), "").unwrap();
+        fs::write(season.join("Dark.S02E03.mkv"), "").unwrap();
+        let media = vec![dir.to_str().unwrap().to_string()];
+        let found = find_episode(&media, 2, 3).unwrap();
+        assert!(found.ends_with("Dark.S02E03.mkv"));
+        assert_eq!(find_episode(&media, 2, 4), None);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_movie() {
+        let dir = temp_dir("movie");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("Face.Off.1997.1080p.mkv"), "").unwrap();
+        let media = vec![dir.to_str().unwrap().to_string()];
+        assert!(find_movie(&media, "Face/Off").unwrap().ends_with("Face.Off.1997.1080p.mkv"));
+        // the only video is the movie, whatever its name
+        assert!(find_movie(&media, "Inception").is_some());
+        fs::write(dir.join("Extras.mkv"), "").unwrap();
+        assert!(find_movie(&media, "Face/Off").unwrap().ends_with("Face.Off.1997.1080p.mkv"));
+        assert_eq!(find_movie(&media, "Inception"), None);
+        let media = vec!["https://example.com/movie.mkv".to_string()];
+        assert_eq!(find_movie(&media, "Inception").as_deref(), Some("https://example.com/movie.mkv"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
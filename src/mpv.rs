@@ -0,0 +1,145 @@
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::UnixStream,
+    path::Path,
+    thread,
+    time::{Duration, Instant},
+};
+
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+// mpv may still be starting when bw is launched alongside it
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A connection to mpv's json ipc, as opened with `mpv --input-ipc-server=SOCKET`
+pub struct Mpv {
+    stream: BufReader<UnixStream>,
+    request_id: u64,
+}
+
+impl Mpv {
+    pub fn connect(socket: &Path) -> io::Result<Self> {
+        let start = Instant::now();
+        loop {
+            match UnixStream::connect(socket) {
+                Ok(stream) => {
+                    return Ok(Self {
+                        stream: BufReader::new(stream),
+                        request_id: 0,
+                    })
+                }
+                Err(e) if start.elapsed() < CONNECT_TIMEOUT
+                    && matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused) =>
+                {
+                    thread::sleep(Duration::from_millis(100));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Value of a property of the player, `None` when it's unavailable (like `path` with nothing
+    /// playing). Fails with `UnexpectedEof` once mpv quits
+    pub fn get_property(&mut self, name: &str) -> io::Result<Option<Value>> {
+        self.request_id += 1;
+        let request = json!({"command": ["get_property", name], "request_id": self.request_id});
+        let stream = self.stream.get_mut();
+        stream.write_all(format!("{request}\n").as_bytes())?;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.stream.read_line(&mut line)? == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            // events are sent on the same socket, between replies
+            let Ok(reply) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if reply.get("request_id").and_then(Value::as_u64) != Some(self.request_id) {
+                continue;
+            }
+            if reply.get("error").and_then(Value::as_str) != Some("success") {
+                return Ok(None);
+            }
+            return Ok(reply.get("data").cloned());
+        }
+    }
+}
+
+/// Polls the player at `socket` every `interval` until it quits, calling `on_finished` with the
+/// path of every file played past `threshold` percent. Each file is reported once per time it's
+/// loaded
+pub fn watch(socket: &Path, threshold: f64, interval: Duration, mut on_finished: impl FnMut(&str)) -> io::Result<()> {
+    let mut mpv = Mpv::connect(socket)?;
+    eprintln!("INFO: Watching mpv at {}", socket.to_str().unwrap());
+    let mut current: Option<String> = None;
+    let mut reported = false;
+    loop {
+        let state = mpv.get_property("path").and_then(|path| Ok((path, mpv.get_property("percent-pos")?)));
+        let (path, position) = match state {
+            Ok(state) => state,
+            Err(e) if matches!(e.kind(), io::ErrorKind::UnexpectedEof | io::ErrorKind::BrokenPipe | io::ErrorKind::ConnectionReset) => {
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        let path = path.as_ref().and_then(Value::as_str);
+        if path != current.as_deref() {
+            current = path.map(str::to_string);
+            reported = false;
+        }
+        let position = position.as_ref().and_then(Value::as_f64).unwrap_or(0.0);
+        if let Some(path) = current.as_deref() {
+            if !reported && position >= threshold {
+                reported = true;
+                on_finished(path);
+            }
+        }
+        thread::sleep(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::temp_dir;
+    use std::{fs, os::unix::net::UnixListener};
+
+    /// Answers like mpv would while playing `path` at each of `positions`, then quits
+    fn fake_mpv(socket: &Path, path: &'static str, positions: Vec<f64>) -> thread::JoinHandle<()> {
+        let listener = UnixListener::bind(socket).unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut writer = stream;
+            let mut positions = positions.into_iter();
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 0 {
+                let request: Value = serde_json::from_str(&line).unwrap();
+                let id = &request["request_id"];
+                let reply = match request["command"][1].as_str().unwrap() {
+                    "path" => json!({"data": path, "request_id": id, "error": "success"}),
+                    _ => match positions.next() {
+                        Some(position) => json!({"data": position, "request_id": id, "error": "success"}),
+                        None => break,
+                    },
+                };
+                writeln!(writer, "{}", json!({"event": "playback-restart"})).unwrap();
+                writeln!(writer, "{reply}").unwrap();
+                line.clear();
+            }
+        })
+    }
+
+    #[test]
+    fn test_watch() {
+        let socket = temp_dir("mpv");
+        let _ = fs::remove_file(&socket);
+        let mpv = fake_mpv(&socket, "/tv/Dark.S01E03.mkv", vec![10.0, 89.9, 90.5, 97.0]);
+        let mut finished = vec![];
+        watch(&socket, 90.0, Duration::from_millis(1), |path| finished.push(path.to_string())).unwrap();
+        mpv.join().unwrap();
+        assert_eq!(finished, ["/tv/Dark.S01E03.mkv"]);
+        fs::remove_file(socket).unwrap();
+    }
+}
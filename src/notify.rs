@@ -0,0 +1,78 @@
+use std::{io, process::Command};
+
+use crate::{serie::Serie, utils};
+
+/// Last episode of a serie, skipping seasons without episodes yet
+pub fn last_episode(serie: &Serie) -> Option<(usize, usize)> {
+    let seasons = serie.seasons();
+    let index = seasons.iter().rposition(|season| season.episodes > 0)?;
+    Some((index + 1, seasons[index].episodes))
+}
+
+/// Episodes of `fresh` after the last one announced. The stored seasons don't count, as series
+/// added from a source already have the episodes that haven't aired yet
+pub fn new_episodes(announced: (usize, usize), fresh: &Serie) -> Vec<(usize, usize)> {
+    fresh
+        .seasons()
+        .iter()
+        .zip(1..)
+        .flat_map(|(season, number)| (1..=season.episodes).map(move |episode| (number, episode)))
+        .filter(|&episode| episode > announced)
+        .collect()
+}
+
+/// Title and body of the notification about `episodes` of a serie
+pub fn announcement(name: &str, episodes: &[(usize, usize)]) -> (String, String) {
+    let format = |(season, episode): (usize, usize)| format!("S{season:02}E{episode:02}");
+    let body = match episodes {
+        [] => String::new(),
+        [episode] => format!("New episode: {}", format(*episode)),
+        [first, .., last] => format!("{} new episodes: {} to {}", episodes.len(), format(*first), format(*last)),
+    };
+    (name.to_string(), body)
+}
+
+/// Runs `command` (like `notify-send --app-name=bw`) with the title and body as its last arguments
+pub fn send(command: &str, title: &str, body: &str) -> io::Result<()> {
+    let words = utils::split_command(command)
+        .ok_or(io::Error::new(io::ErrorKind::InvalidInput, "The notify command has an unclosed quote"))?;
+    let (program, args) = words
+        .split_first()
+        .ok_or(io::Error::new(io::ErrorKind::InvalidInput, "The notify command is empty"))?;
+    let status = Command::new(program).args(args).arg(title).arg(body).status()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("{command} exited with {status}")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    #[test]
+    fn test_new_episodes() {
+        let fresh = serie("0/8\n0/10\n0/2", "Dark");
+        assert_eq!(new_episodes((2, 10), &fresh), [(3, 1), (3, 2)]);
+        assert_eq!(new_episodes((3, 2), &fresh), []);
+        assert_eq!(new_episodes((3, 1), &fresh), [(3, 2)]);
+    }
+
+    #[test]
+    fn test_new_episodes_not_aired() {
+        // added with the whole season scheduled, of which only 5 episodes aired by now
+        let stored = serie("announced: S02E03\n8/8\n3/10", "Dark");
+        let aired = serie("0/8\n0/5", "Dark");
+        assert_eq!(new_episodes(stored.announced.unwrap(), &aired), [(2, 4), (2, 5)]);
+        assert_eq!(last_episode(&aired), Some((2, 5)));
+        assert_eq!(last_episode(&serie("0/8\n0/0", "Dark")), Some((1, 8)));
+        assert_eq!(last_episode(&serie("0/0", "Dark")), None);
+    }
+
+    #[test]
+    fn test_announcement() {
+        assert_eq!(announcement("Dark", &[(3, 1)]).1, "New episode: S03E01");
+        assert_eq!(announcement("Dark", &[(3, 1), (3, 2), (3, 3)]).1, "3 new episodes: S03E01 to S03E03");
+    }
+}
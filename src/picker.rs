@@ -0,0 +1,93 @@
+use std::io::{self, BufRead, Write};
+
+use crate::serie::Serie;
+
+/// Parses a selection like `1 3-5,7` (numbers start from 1) into indexes of a list of `len`
+/// items. `all` or `*` selects everything
+pub fn parse_selection(input: &str, len: usize) -> Option<Vec<usize>> {
+    let input = input.trim();
+    if input == "all" || input == "*" {
+        return Some((0..len).collect());
+    }
+    let mut indexes = vec![];
+    for part in input.split([' ', ',']).filter(|part| !part.is_empty()) {
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (start.parse::<usize>().ok()?, end.parse::<usize>().ok()?),
+            None => {
+                let number = part.parse::<usize>().ok()?;
+                (number, number)
+            }
+        };
+        if start == 0 || start > end || end > len {
+            return None;
+        }
+        for index in start - 1..end {
+            if !indexes.contains(&index) {
+                indexes.push(index);
+            }
+        }
+    }
+    if indexes.is_empty() {
+        None
+    } else {
+        Some(indexes)
+    }
+}
+
+/// Prints a numbered menu of `series` to stderr and lets the user pick one or more of them.
+/// Typing anything that isn't a selection filters the menu by name. Empty input picks nothing
+pub fn pick<T>(series: Vec<(Serie, T)>, title: &str) -> Vec<(Serie, T)> {
+    let stdin = io::stdin();
+    let mut input = String::new();
+    let mut shown: Vec<usize> = (0..series.len()).collect();
+    loop {
+        eprintln!("Select series to {title}:");
+        for (number, &index) in (1..).zip(&shown) {
+            let serie = &series[index].0;
+            eprintln!("{number:>3}) {} {}", serie.name, serie.next_episode_flat());
+        }
+        eprint!("Numbers or ranges (e.g. 1 3-5), text to filter, empty to cancel: ");
+        let _ = io::stderr().flush();
+        input.clear();
+        if stdin.lock().read_line(&mut input).unwrap_or(0) == 0 || input.trim().is_empty() {
+            return vec![];
+        }
+        if let Some(selection) = parse_selection(&input, shown.len()) {
+            let selected: Vec<usize> = selection.into_iter().map(|i| shown[i]).collect();
+            return series
+                .into_iter()
+                .enumerate()
+                .filter(|(i, _)| selected.contains(i))
+                .map(|(_, s_p)| s_p)
+                .collect();
+        }
+        let query = input.trim();
+        shown = (0..series.len()).filter(|&i| series[i].0.matches(query)).collect();
+        if shown.is_empty() {
+            eprintln!("Nothing matches \"{query}\"");
+            shown = (0..series.len()).collect();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("2", 3), Some(vec![1]));
+        assert_eq!(parse_selection("1 3-5,7", 7), Some(vec![0, 2, 3, 4, 6]));
+        assert_eq!(parse_selection("2 2 1-2", 3), Some(vec![1, 0]));
+        assert_eq!(parse_selection("all", 3), Some(vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn test_parse_selection_invalid() {
+        assert_eq!(parse_selection("0", 3), None);
+        assert_eq!(parse_selection("4", 3), None);
+        assert_eq!(parse_selection("3-1", 3), None);
+        assert_eq!(parse_selection("breaking", 3), None);
+        assert_eq!(parse_selection("", 3), None);
+    }
+}
@@ -0,0 +1,180 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    media,
+    serie::{Season, Serie},
+};
+
+#[derive(Default)]
+struct ScannedShow {
+    directory: Option<PathBuf>,
+    // last episode of each season
+    seasons: BTreeMap<usize, usize>,
+}
+
+/// Season number of a directory named like `Season 02`, `season2` or `S02`
+fn season_of_dir(name: &str) -> Option<usize> {
+    let name = name.trim().to_ascii_lowercase();
+    let number = name
+        .strip_prefix("season")
+        .or_else(|| name.strip_prefix('s'))?
+        .trim_start_matches([' ', '.', '_', '-']);
+    number.parse().ok()
+}
+
+/// Episode number of a file named like `E05 - Title.mkv`, `Episode 5.mkv` or `05 - Title.mkv`
+fn episode_number_of(name: &str) -> Option<usize> {
+    let name = name.trim().to_ascii_lowercase();
+    let number = name
+        .strip_prefix("episode")
+        .or_else(|| name.strip_prefix('e'))
+        .unwrap_or(&name)
+        .trim_start_matches([' ', '.', '_', '-']);
+    let digits: String = number.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Show name from the part of a file name before its episode, like `Breaking Bad` from
+/// `Breaking.Bad.S02E05.mkv`
+fn show_name_of_file(name: &str) -> Option<String> {
+    let (_, _, start) = media::find_episode_pattern(name)?;
+    let show = name[..start]
+        .replace(['.', '_'], " ")
+        .trim_matches([' ', '-'])
+        .to_string();
+    if show.is_empty() {
+        None
+    } else {
+        Some(show)
+    }
+}
+
+/// Show, season and episode of a video at `path`, from its file name (`Show.Name.S02E05.mkv`)
+/// or, when the file name has no show, from its directories (`Show Name/Season 02/S02E05.mkv`)
+pub fn episode_of_path(path: &Path) -> Option<(String, usize, usize)> {
+    let file_name = path.file_stem()?.to_str()?;
+    let (season, episode) = media::episode_of(file_name)?;
+    let show = show_name_of_file(file_name).or_else(|| {
+        path.ancestors()
+            .skip(1)
+            .filter_map(|dir| dir.file_name()?.to_str())
+            .find(|name| season_of_dir(name).is_none())
+            .map(str::to_string)
+    })?;
+    Some((show, season, episode))
+}
+
+/// Builds series out of the video files under `dir`. Files are expected in
+/// `Show Name/Season 02/Show.Name.S02E05.mkv`-like trees, or directly in `dir` when their name
+/// includes the show (like `Show.Name.S02E05.mkv`). Each serie has its directory as media, and as
+/// many episodes in each season as the last episode found of it.
+pub fn scan(dir: &Path) -> Vec<Serie> {
+    let dir = dir.canonicalize().unwrap_or(dir.to_path_buf());
+    let mut shows: BTreeMap<String, ScannedShow> = BTreeMap::new();
+    for video in media::videos_in(&dir) {
+        let Ok(relative) = video.strip_prefix(&dir) else {
+            continue;
+        };
+        let file_name = video.file_stem().and_then(|name| name.to_str()).unwrap_or_default();
+        let mut components = relative.components();
+        let (name, directory) = match (components.next(), components.next()) {
+            (Some(show), Some(_)) => {
+                let show = show.as_os_str().to_str().unwrap_or_default().to_string();
+                let directory = dir.join(&show);
+                (show, Some(directory))
+            }
+            _ => match show_name_of_file(file_name) {
+                Some(show) => (show, None),
+                None => continue,
+            },
+        };
+        let episode = media::episode_of(file_name).or_else(|| {
+            let season = video
+                .parent()
+                .and_then(|parent| parent.file_name())
+                .and_then(|name| season_of_dir(name.to_str()?))?;
+            Some((season, episode_number_of(file_name)?))
+        });
+        // season 0 holds specials
+        let Some((season, episode)) = episode else {
+            continue;
+        };
+        let show = shows.entry(name).or_default();
+        show.directory = show.directory.take().or(directory);
+        let last = show.seasons.entry(season).or_default();
+        *last = episode.max(*last);
+    }
+    shows
+        .into_iter()
+        // shows of only specials have no seasons to track them with
+        .filter(|(_, show)| show.seasons.keys().any(|&season| season > 0))
+        .map(|(name, show)| {
+            let last_season = show.seasons.keys().last().copied().unwrap_or(0);
+            let seasons = (1..=last_season)
+                .map(|season| Season::new(show.seasons.get(&season).copied().unwrap_or(0)))
+                .collect();
+            let mut serie = Serie::new(seasons, name);
+            serie.specials = show.seasons.get(&0).copied().map(Season::new);
+            serie.media.extend(show.directory.and_then(|dir| dir.to_str().map(str::to_string)));
+            serie
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::temp_dir;
+    use std::fs;
+
+    #[test]
+    fn test_names() {
+        assert_eq!(season_of_dir("Season 02"), Some(2));
+        assert_eq!(season_of_dir("S3"), Some(3));
+        assert_eq!(season_of_dir("Extras"), None);
+        assert_eq!(episode_number_of("E05 - Pilot"), Some(5));
+        assert_eq!(episode_number_of("Episode 12"), Some(12));
+        assert_eq!(episode_number_of("07 - Title"), Some(7));
+        assert_eq!(show_name_of_file("Breaking.Bad.S02E05.720p"), Some("Breaking Bad".to_string()));
+        assert_eq!(show_name_of_file("S02E05"), None);
+    }
+
+    #[test]
+    fn test_episode_of_path() {
+        let expected = Some(("Breaking Bad".to_string(), 2, 5));
+        assert_eq!(episode_of_path(Path::new("/tv/Breaking.Bad.S02E05.mkv")), expected);
+        assert_eq!(episode_of_path(Path::new("/tv/Breaking Bad/Season 02/S02E05.mkv")), expected);
+        assert_eq!(episode_of_path(Path::new("https://example.com/Breaking.Bad.S02E05.mkv")), expected);
+        assert_eq!(episode_of_path(Path::new("/tv/Breaking Bad/Pilot.mkv")), None);
+    }
+
+    #[test]
+    fn test_scan() {
+        let dir = temp_dir("scan");
+        for file in [
+            "Breaking Bad/Season 01/Breaking.Bad.S01E01.mkv",
+            "Breaking Bad/Season 01/Breaking.Bad.S01E07.mkv",
+            "Breaking Bad/Season 02/Breaking.Bad.S02E03.mkv",
+            "Breaking Bad/Season 02/Breaking.Bad.S02E03.srt",
+            "Breaking Bad/Specials/Breaking.Bad.S00E02.mkv",
+            "Minisodes.S00E01.mkv",
+            "Dark/Season 2/E04 - Lost and Found.mp4",
+            "Fargo.1x05.mkv",
+        ] {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+        let series = scan(&dir);
+        let names: Vec<&str> = series.iter().map(|serie| serie.name.as_str()).collect();
+        assert_eq!(names, ["Breaking Bad", "Dark", "Fargo"]);
+        assert_eq!(series[0].to_string().lines().skip(1).collect::<Vec<_>>(), ["specials: 0/2", "0/7", "0/3"]);
+        assert!(series[0].media[0].ends_with("Breaking Bad"));
+        assert_eq!(series[1].to_string().lines().skip(1).collect::<Vec<_>>(), ["0/0", "0/4"]);
+        assert_eq!(series[2].to_string(), "0/5\n");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -3,16 +3,20 @@
 mod season;
 use core::fmt;
 use std::{
-    fs::{self, File},
-    io::{self, Write},
+    cmp::Ordering,
+    fs,
+    io,
     path::{Path, PathBuf},
     str::FromStr,
 };
 use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
 pub use season::Season;
+use crate::utils;
 //}}}
 
-#[derive(Debug, Clone, ValueEnum)]
+#[derive(Debug, Clone, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub enum PrintMode {
     Normal,
     #[value(alias="x")]
@@ -31,11 +35,170 @@ pub enum PrintMode {
     Content,
 }
 
-#[derive(Debug, Default, PartialEq)]
+/// What a serie is, from a `kind:` line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    Series,
+    /// A single item, watched or not
+    Movie,
+    Miniseries,
+    Ova,
+}
+
+impl Kind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Series => "series",
+            Kind::Movie => "movie",
+            Kind::Miniseries => "miniseries",
+            Kind::Ova => "ova",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Kind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// How episodes of a serie are numbered, from a `numbering:` line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Numbering {
+    /// Like S02E05
+    Seasons,
+    /// Counting from the first episode of the first season, like 1071
+    Absolute,
+}
+
+impl Numbering {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Numbering::Seasons => "seasons",
+            Numbering::Absolute => "absolute",
+        }
+    }
+}
+
+impl fmt::Display for Numbering {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Numbering {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+/// Where a serie is in its lifecycle, from a `state:` line
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum State {
+    Watching,
+    /// On hold, to continue later
+    Paused,
+    /// Given up on
+    Dropped,
+    /// Not started yet
+    #[value(alias = "planned")]
+    PlanToWatch,
+    Completed,
+}
+
+impl State {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            State::Watching => "watching",
+            State::Paused => "paused",
+            State::Dropped => "dropped",
+            State::PlanToWatch => "plan-to-watch",
+            State::Completed => "completed",
+        }
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for State {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <Self as ValueEnum>::from_str(s, true)
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Serie {
-    seasons: Vec<Season>,
     pub name: String,
+    seasons: Vec<Season>,
+    #[serde(skip)]
     current_season: Option<usize>,
+    /// Series, unless a `kind:` line says otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<Kind>,
+    /// Seasons, unless a `numbering:` line says otherwise
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub numbering: Option<Numbering>,
+    /// Directories, video files or urls to find episodes in, from `media:` lines
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub media: Vec<String>,
+    /// Tags to organize series with, from a `tags: anime, rewatch` line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Lifecycle state set with `bw state`, from a `state: paused` line. Without it, series are
+    /// watching until finished, then completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<State>,
+    /// From 1 to 10, from a `rating:` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    /// Personal notes, from a `notes:` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Url or path of an image of the serie, from a `poster:` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poster: Option<String>,
+    /// Where to fetch the serie from, like `episodate:breaking-bad`, from a `source:` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    /// Last new episode `bw notify` announced, from an `announced: S02E05` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub announced: Option<(usize, usize)>,
+    /// When an episode was last watched, like `2024-05-30T21:04:05Z`, from a `last-watched:` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_watched: Option<String>,
+    /// Dates of the times the serie was watched through before `bw rewatch`, from
+    /// `watched-through:` lines
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub watched_through: Vec<String>,
+    /// Episodes out of the seasons, like S00E03, from a `specials: 1/4` line
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub specials: Option<Season>,
+    /// Whether the next episode, watching and progress take the specials into account. They come
+    /// before the seasons
+    #[serde(skip)]
+    include_specials: bool,
+    /// `key: value` lines this version doesn't know about, kept as they are
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    extra: Vec<(String, String)>,
 }
 
 #[inline(always)]
@@ -54,6 +217,48 @@ fn number_width(mut number: usize) -> usize {
 
 impl fmt::Display for Serie {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(kind) = self.kind {
+            writeln!(f, "kind: {kind}")?;
+        }
+        if let Some(numbering) = self.numbering {
+            writeln!(f, "numbering: {numbering}")?;
+        }
+        for media in &self.media {
+            writeln!(f, "media: {media}")?;
+        }
+        if !self.tags.is_empty() {
+            writeln!(f, "tags: {}", self.tags.join(", "))?;
+        }
+        if let Some(state) = self.state {
+            writeln!(f, "state: {state}")?;
+        }
+        if let Some(rating) = self.rating {
+            writeln!(f, "rating: {rating}")?;
+        }
+        if let Some(notes) = &self.notes {
+            writeln!(f, "notes: {notes}")?;
+        }
+        if let Some(poster) = &self.poster {
+            writeln!(f, "poster: {poster}")?;
+        }
+        if let Some(source) = &self.source {
+            writeln!(f, "source: {source}")?;
+        }
+        if let Some((season, episode)) = self.announced {
+            writeln!(f, "announced: S{season:02}E{episode:02}")?;
+        }
+        if let Some(last_watched) = &self.last_watched {
+            writeln!(f, "last-watched: {last_watched}")?;
+        }
+        for date in &self.watched_through {
+            writeln!(f, "watched-through: {date}")?;
+        }
+        if let Some(specials) = &self.specials {
+            writeln!(f, "specials: {specials}")?;
+        }
+        for (key, value) in &self.extra {
+            writeln!(f, "{key}: {value}")?;
+        }
         for season in &self.seasons {
             writeln!(f, "{}", season)?;
         }
@@ -61,6 +266,22 @@ impl fmt::Display for Serie {
     }
 }
 
+/// Parses an episode in the `S01E05` format, as printed by the next-episode print mode
+pub fn parse_episode(s: &str) -> Option<(usize, usize)> {
+    let s = s.trim().to_lowercase();
+    let (season, episode) = s.strip_prefix('s')?.split_once('e')?;
+    Some((season.parse().ok()?, episode.parse().ok()?))
+}
+
+/// What [`Serie::watch_until`] did
+#[derive(Debug, PartialEq)]
+pub enum WatchUntil {
+    Watched,
+    /// The episode was already watched
+    AlreadyPast,
+    NoSuchEpisode,
+}
+
 #[derive(Debug)]
 pub enum SerieParseError {
     EmptyFile,
@@ -72,7 +293,52 @@ impl FromStr for Serie {
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
         let mut seasons = Vec::with_capacity(value.lines().count());
+        let mut serie = Serie::default();
         for line in value.lines() {
+            // metadata lines, like "media: /path/to/serie". season lines start with a number, and
+            // their notes can have colons
+            let metadata = line.split_once(':').filter(|_| !line.starts_with(|c: char| c.is_ascii_digit()));
+            if let Some((key, value)) = metadata {
+                let value = value.trim().to_string();
+                match key.trim() {
+                    "media" => serie.media.push(value),
+                    "kind" => match value.parse() {
+                        Ok(kind) => serie.kind = Some(kind),
+                        Err(_) => serie.extra.push(("kind".to_string(), value)),
+                    },
+                    "numbering" => match value.parse() {
+                        Ok(numbering) => serie.numbering = Some(numbering),
+                        Err(_) => serie.extra.push(("numbering".to_string(), value)),
+                    },
+                    "tags" => {
+                        let tags = value.split(',').map(str::trim).filter(|tag| !tag.is_empty());
+                        serie.tags.extend(tags.map(str::to_string));
+                    }
+                    "state" => match value.parse() {
+                        Ok(state) => serie.state = Some(state),
+                        Err(_) => serie.extra.push(("state".to_string(), value)),
+                    },
+                    "rating" => match season::parse_rating(&value) {
+                        Some(rating) => serie.rating = Some(rating),
+                        None => serie.extra.push(("rating".to_string(), value)),
+                    },
+                    "notes" => serie.notes = Some(value),
+                    "poster" => serie.poster = Some(value),
+                    "source" => serie.source = Some(value),
+                    "last-watched" => serie.last_watched = Some(value),
+                    "watched-through" => serie.watched_through.push(value),
+                    "specials" => match value.parse() {
+                        Ok(specials) => serie.specials = Some(specials),
+                        Err(_) => serie.extra.push(("specials".to_string(), value)),
+                    },
+                    "announced" => match parse_episode(&value) {
+                        Some(episode) => serie.announced = Some(episode),
+                        None => serie.extra.push(("announced".to_string(), value)),
+                    },
+                    key => serie.extra.push((key.to_string(), value)),
+                }
+                continue;
+            }
             match line.parse() {
                 Ok(season) => seasons.push(season),
                 Err(season::SeasonError::MalformedSeason) => return Err(SerieParseError::ParseFailed),
@@ -83,7 +349,9 @@ impl FromStr for Serie {
         if seasons.is_empty() {
             return Err(SerieParseError::EmptyFile);
         }
-        Ok(Serie::new(seasons, ""))
+        serie.seasons = seasons;
+        serie.update_current_season();
+        Ok(serie)
     }
 }
 
@@ -94,16 +362,40 @@ impl Serie {
             current_season: seasons.iter().position(Season::is_not_finished),
             seasons,
             name,
+            ..Default::default()
         }
     }
 
+    /// Takes the specials into account, or not (the default)
+    #[inline]
+    pub fn with_specials(mut self, include: bool) -> Self {
+        self.include_specials = include;
+        self
+    }
+
+    /// Whether the next episode is a special
+    #[inline]
+    fn specials_pending(&self) -> bool {
+        self.include_specials && self.specials.as_ref().is_some_and(Season::is_not_finished)
+    }
+
+    #[inline]
+    pub fn update_current_season(&mut self) {
+        self.current_season = self.seasons.iter().position(Season::is_not_finished);
+    }
+
     #[inline]
     pub fn print(&self, print: &PrintMode, path: Option<&PathBuf>) {
         match print {
             PrintMode::Extended => self.print_extended(),
+            // a movie has no episode, so its title is what's next
+            PrintMode::NextEpisode if self.kind() == Kind::Movie => println!("{}", self.name),
             PrintMode::NextEpisode => println!("{}", self.next_episode_str().expect("Serie is finished")),
-            PrintMode::Normal => println!("{} {}", self.name, self.next_episode_flat()),
+            PrintMode::Normal => println!("{}", self.normal()),
             PrintMode::Season => println!("{}", self.next_season()),
+            PrintMode::Episode if self.numbering() == Numbering::Absolute && !self.specials_pending() => {
+                println!("{}", self.absolute_of(self.next_season(), self.next_episode()).unwrap_or(0))
+            }
             PrintMode::Episode => println!("{}", self.next_episode()),
             PrintMode::Path => println!("{}", path.unwrap().to_str().unwrap()),
             PrintMode::Name => println!("{}", self.name),
@@ -111,16 +403,85 @@ impl Serie {
         }
     }
 
+    /// Name and next episode (with which rewatch it is), then the state when it isn't the usual one, and the tags
+    pub fn normal(&self) -> String {
+        let mut out = format!("{} {}", self.name, self.next_episode_rewatch());
+        if self.state() != self.default_state() {
+            out += &format!(" ({})", self.state());
+        }
+        if !self.tags.is_empty() {
+            out += &format!(" [{}]", self.tags.join(", "));
+        }
+        out
+    }
+
+    /// The next episode, or whether a movie is watched
+    #[inline]
+    pub fn next_episode_flat(&self) -> String {
+        match (self.kind(), self.next_episode_str()) {
+            (Kind::Movie, Some(_)) => "UNWATCHED".to_string(),
+            (Kind::Movie, None) => "WATCHED".to_string(),
+            (_, next_episode) => next_episode.unwrap_or("FINISHED".to_string()),
+        }
+    }
+
     #[inline]
-    fn next_episode_flat(&self) -> String {
-        self.next_episode_str().unwrap_or("FINISHED".to_string())
+    pub fn kind(&self) -> Kind {
+        self.kind.unwrap_or(Kind::Series)
     }
 
     #[inline]
-    pub fn write(&self, path: PathBuf) -> io::Result<()> {
-        let mut file = File::create(path)?;
-        write!(file, "{}", self)?;
-        Ok(())
+    pub fn numbering(&self) -> Numbering {
+        self.numbering.unwrap_or(Numbering::Seasons)
+    }
+
+    /// Absolute number of `episode` of `season` (both starting from 1), counting the episodes of
+    /// the seasons before it
+    pub fn absolute_of(&self, season: usize, episode: usize) -> Option<usize> {
+        let index = season.checked_sub(1)?;
+        let episodes = self.seasons.get(index)?.episodes;
+        if episode == 0 || episode > episodes {
+            return None;
+        }
+        Some(self.seasons[..index].iter().map(|season| season.episodes).sum::<usize>() + episode)
+    }
+
+    /// Season and episode of the absolute episode `absolute`, the opposite of
+    /// [`Serie::absolute_of`]
+    pub fn episode_of_absolute(&self, absolute: usize) -> Option<(usize, usize)> {
+        let mut episode = absolute.checked_sub(1)?;
+        for (season, number) in self.seasons.iter().zip(1..) {
+            if episode < season.episodes {
+                return Some((number, episode + 1));
+            }
+            episode -= season.episodes;
+        }
+        None
+    }
+
+    /// Season and episode of `s`, like `S02E05`, or an absolute number like `1071` when the serie
+    /// is numbered absolutely
+    pub fn find_episode(&self, s: &str) -> Option<(usize, usize)> {
+        match self.numbering() {
+            Numbering::Absolute => parse_episode(s).or_else(|| self.episode_of_absolute(s.trim().parse().ok()?)),
+            Numbering::Seasons => parse_episode(s),
+        }
+    }
+
+    /// Like [`Serie::next_episode_flat`], with which rewatch it is, like `Rewatch #2, S01E04`
+    pub fn next_episode_rewatch(&self) -> String {
+        let episode = self.next_episode_flat();
+        if self.watched_through.is_empty() || self.is_finished() {
+            return episode;
+        }
+        format!("Rewatch #{}, {episode}", self.watched_through.len())
+    }
+
+    /// Atomically replaces the file at `path` with the serie, so an interrupted write never
+    /// leaves a truncated file behind
+    #[inline]
+    pub fn write(&self, path: &Path) -> io::Result<()> {
+        utils::write_atomic(path, self.to_string().as_bytes())
     }
 
     #[inline]
@@ -142,7 +503,7 @@ impl Serie {
     #[inline]
     pub fn is_finished(&self) -> bool {
         if let Some(season) = self.seasons.last() {
-            season.episodes == season.watched
+            season.episodes == season.watched && !self.specials_pending()
         } else {
             true
         }
@@ -153,13 +514,20 @@ impl Serie {
         !self.is_finished()
     }
 
+    /// Season of the next episode, 0 for specials
     #[inline]
     pub fn next_season(&self) -> usize {
+        if self.specials_pending() {
+            return 0;
+        }
         self.current_season.map(|i| i+1).unwrap_or(0)
     }
 
     #[inline]
     pub fn next_episode(&self) -> usize {
+        if let Some(specials) = self.specials.as_ref().filter(|_| self.specials_pending()) {
+            return specials.watched + 1;
+        }
         match self.current_season() {
             Some(season) => season.watched + 1,
             None => 1,
@@ -168,6 +536,16 @@ impl Serie {
 
     #[inline]
     pub fn next_episode_str(&self) -> Option<String> {
+        if let Some(specials) = self.specials.as_ref().filter(|_| self.specials_pending()) {
+            let episode_width = number_width(specials.episodes);
+            let season_width = number_width(self.seasons.len());
+            return Some(format!("S{:0season_width$}E{:0episode_width$}", 0, specials.watched + 1));
+        }
+        if self.numbering() == Numbering::Absolute {
+            let absolute = self.absolute_of(self.next_season(), self.next_episode())?;
+            let width = number_width(self.seasons.iter().map(|season| season.episodes).sum());
+            return Some(format!("{absolute:0width$}"));
+        }
         let season = self.current_season()?;
         let episode_width = number_width(season.episodes);
         let season_width = number_width(self.seasons.len());
@@ -178,6 +556,23 @@ impl Serie {
         ))
     }
 
+    #[inline]
+    pub fn seasons(&self) -> &[Season] {
+        &self.seasons
+    }
+
+    /// The `key: value` lines this version doesn't know about
+    #[inline]
+    pub fn extra(&self) -> &[(String, String)] {
+        &self.extra
+    }
+
+    /// Season `number`, starting from 1
+    #[inline]
+    pub fn season_mut(&mut self, number: usize) -> Option<&mut Season> {
+        self.seasons.get_mut(number.checked_sub(1)?)
+    }
+
     #[inline]
     pub fn current_season(&self) -> Option<&Season> {
         let index = self.current_season?;
@@ -186,42 +581,104 @@ impl Serie {
 
     #[inline]
     pub fn print_extended(&self) {
-        println!(
+        print!("{}", self.extended());
+    }
+
+    pub fn extended(&self) -> String {
+        if self.kind() == Kind::Movie {
+            return self.extended_movie();
+        }
+        let mut out = format!(
             "Name: {}
 Percentage: {:.2}%
 Watched/Total: {}/{}
 Next episode: {}
+State: {}
 ",
             self.name,
             self.watched_percentage(),
             self.total_watched(),
             self.total_episodes(),
-            self.next_episode_flat(),
+            self.next_episode_rewatch(),
+            self.state(),
         );
+        if self.kind() != Kind::Series {
+            out += &format!("Kind: {}\n", self.kind());
+        }
+        if self.numbering() != Numbering::Seasons {
+            out += &format!("Numbering: {}\n", self.numbering());
+        }
+        out += &self.extended_details();
+        out += "\n";
+        if let Some(specials) = &self.specials {
+            out += &format!("0: {specials}\n");
+        }
         for (season, i) in self.seasons.iter().zip(1..) {
-            println!("{}: {}",i, season);
+            out += &format!("{}: {}\n", i, season);
+        }
+        out
+    }
+
+    /// Movies have no seasons to show
+    fn extended_movie(&self) -> String {
+        let mut out = format!(
+            "Name: {}
+Kind: movie
+Watched: {}
+State: {}
+",
+            self.name,
+            if self.is_finished() { "yes" } else { "no" },
+            self.state(),
+        );
+        out += &self.extended_details();
+        out
+    }
+
+    /// Lines of the extended print mode that are the same for every kind
+    fn extended_details(&self) -> String {
+        let mut out = String::new();
+        if !self.watched_through.is_empty() {
+            out += &format!("Watched through: {}\n", self.watched_through.join(", "));
+        }
+        if let Some(rating) = self.rating {
+            out += &format!("Rating: {rating}/10\n");
         }
+        if let Some(notes) = &self.notes {
+            out += &format!("Notes: {notes}\n");
+        }
+        if !self.tags.is_empty() {
+            out += &format!("Tags: {}\n", self.tags.join(", "));
+        }
+        out
     }
 
     #[inline]
     pub fn unwatch(&mut self, count: usize) -> usize {
         let mut unwatch_count = count;
-        let mut index = self.current_season.unwrap_or(self.seasons.len()-1);
-
-        loop {
-            unwatch_count = self.seasons[index].unwatch(unwatch_count);
-            if unwatch_count == 0 || index == 0 {
-                break;
+        // series without seasons only come from code, but shouldn't panic either
+        if let Some(mut index) = self.current_season.or(self.seasons.len().checked_sub(1)) {
+            loop {
+                unwatch_count = self.seasons[index].unwatch(unwatch_count);
+                if unwatch_count == 0 || index == 0 {
+                    break;
+                }
+                index -= 1;
             }
-            index -= 1;
+            self.current_season = Some(index);
+        }
+        if let Some(specials) = self.specials.as_mut().filter(|_| self.include_specials) {
+            unwatch_count = specials.unwatch(unwatch_count);
         }
-        self.current_season = Some(index);
         unwatch_count
     }
 
     #[inline]
     pub fn watch(&mut self, count: usize) -> usize {
         let mut watch_count = count;
+        if let Some(specials) = self.specials.as_mut().filter(|_| self.include_specials) {
+            watch_count = specials.watch(watch_count);
+        }
         if let Some(mut index) = self.current_season {
             while index < self.seasons.len() && watch_count > 0 {
                 watch_count = self.seasons[index].watch(watch_count);
@@ -238,6 +695,160 @@ Next episode: {}
         watch_count
     }
 
+    /// Values of the `key: value` lines of the serie with `key`
+    pub fn metadata(&self, key: &str) -> Vec<&str> {
+        match key {
+            "media" => self.media.iter().map(String::as_str).collect(),
+            "tag" | "tags" => self.tags.iter().map(String::as_str).collect(),
+            "state" => vec![self.state().as_str()],
+            "kind" => vec![self.kind().as_str()],
+            "numbering" => vec![self.numbering().as_str()],
+            "notes" => self.notes.iter().map(String::as_str).collect(),
+            "poster" => self.poster.iter().map(String::as_str).collect(),
+            "source" => self.source.iter().map(String::as_str).collect(),
+            "last-watched" => self.last_watched.iter().map(String::as_str).collect(),
+            "watched-through" => self.watched_through.iter().map(String::as_str).collect(),
+            key => self
+                .extra
+                .iter()
+                .filter(|(extra_key, _)| extra_key == key)
+                .map(|(_, value)| value.as_str())
+                .collect(),
+        }
+    }
+
+    #[inline]
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    /// Adds `tag` unless the serie already has it. Returns whether it was added, or why it can't
+    /// be a tag: `tags:` is a single line, split on commas
+    pub fn add_tag(&mut self, tag: &str) -> Result<bool, String> {
+        if tag.trim().is_empty() || tag.contains(['\n', '\r', ',']) {
+            return Err(format!("\"{}\" can't be a tag, as tags can't be empty or have commas or line breaks", tag.escape_debug()));
+        }
+        if self.has_tag(tag) {
+            return Ok(false);
+        }
+        self.tags.push(tag.to_string());
+        Ok(true)
+    }
+
+    /// Returns whether the serie had `tag`
+    pub fn remove_tag(&mut self, tag: &str) -> bool {
+        let len = self.tags.len();
+        self.tags.retain(|own| !own.eq_ignore_ascii_case(tag));
+        self.tags.len() != len
+    }
+
+    /// State of a serie without a `state:` line
+    #[inline]
+    fn default_state(&self) -> State {
+        if self.is_finished() { State::Completed } else { State::Watching }
+    }
+
+    #[inline]
+    pub fn state(&self) -> State {
+        self.state.unwrap_or_else(|| self.default_state())
+    }
+
+    /// Sets the state, dropping the `state:` line when it's the one the serie has anyway.
+    /// Returns whether anything changed
+    pub fn set_state(&mut self, state: State) -> bool {
+        let state = Some(state).filter(|&state| state != self.default_state());
+        let changed = self.state != state;
+        self.state = state;
+        changed
+    }
+
+    /// Records now as when an episode was last watched. Starting a planned serie makes it
+    /// watching
+    #[inline]
+    pub fn mark_watched(&mut self) {
+        self.last_watched = Some(utils::now());
+        if self.state == Some(State::PlanToWatch) {
+            self.state = None;
+        }
+    }
+
+    /// Records a pass of a finished (or completed) serie, on the day an episode was last watched,
+    /// and starts it over. Returns false if the serie is neither
+    pub fn rewatch(&mut self) -> bool {
+        if self.is_not_finished() && self.state() != State::Completed {
+            return false;
+        }
+        let date = self.last_watched.as_deref().and_then(|last_watched| last_watched.get(..10));
+        self.watched_through.push(date.map_or_else(utils::today, str::to_string));
+        for season in self.seasons.iter_mut().chain(&mut self.specials) {
+            season.watched = 0;
+        }
+        self.update_current_season();
+        self.state = None;
+        true
+    }
+
+    /// Marks everything before `episode` of `season` (both starting from 1) as watched, and
+    /// everything after as not watched. Season 0 seeks through the specials alone. Returns false if
+    /// there is no such episode
+    pub fn seek(&mut self, season: usize, episode: usize) -> bool {
+        if season == 0 {
+            return match &mut self.specials {
+                Some(specials) if episode >= 1 && episode <= specials.episodes => {
+                    specials.watched = episode - 1;
+                    true
+                }
+                _ => false,
+            };
+        }
+        let in_range = season
+            .checked_sub(1)
+            .and_then(|index| self.seasons.get(index))
+            .is_some_and(|s| episode >= 1 && episode <= s.episodes);
+        if !in_range {
+            return false;
+        }
+        for (s, i) in self.seasons.iter_mut().zip(1..) {
+            s.watched = match i.cmp(&season) {
+                Ordering::Less => s.episodes,
+                Ordering::Equal => episode - 1,
+                Ordering::Greater => 0,
+            };
+        }
+        self.update_current_season();
+        true
+    }
+
+    /// Marks everything up to `episode` of `season` as watched, unless it's already watched
+    pub fn watch_until(&mut self, season: usize, episode: usize) -> WatchUntil {
+        if season == 0 {
+            return match &mut self.specials {
+                Some(specials) if episode == 0 || episode > specials.episodes => WatchUntil::NoSuchEpisode,
+                Some(specials) if episode > specials.watched => {
+                    specials.watched = episode;
+                    WatchUntil::Watched
+                }
+                Some(_) => WatchUntil::AlreadyPast,
+                None => WatchUntil::NoSuchEpisode,
+            };
+        }
+        let exists = season
+            .checked_sub(1)
+            .and_then(|index| self.seasons.get(index))
+            .is_some_and(|s| episode >= 1 && episode <= s.episodes);
+        if !exists {
+            return WatchUntil::NoSuchEpisode;
+        }
+        if self.is_finished() || (season, episode) < (self.next_season(), self.next_episode()) {
+            return WatchUntil::AlreadyPast;
+        }
+        self.seek(season, episode);
+        // not watch(1), which would watch a pending special instead
+        self.seasons[season - 1].watched = episode;
+        self.update_current_season();
+        WatchUntil::Watched
+    }
+
     #[inline]
     pub fn filename(&self) -> String {
         format!("{}.bw", self.name)
@@ -248,14 +859,20 @@ Next episode: {}
         self.total_watched() as f32 / self.total_episodes() as f32 * 100.
     }
 
+    /// The seasons, after the specials when they're included
+    #[inline]
+    fn counted_seasons(&self) -> impl Iterator<Item = &Season> {
+        self.specials.iter().filter(|_| self.include_specials).chain(&self.seasons)
+    }
+
     #[inline]
     pub fn total_watched(&self) -> usize {
-        self.seasons.iter().map(|season| season.watched).sum()
+        self.counted_seasons().map(|season| season.watched).sum()
     }
 
     #[inline]
     pub fn total_episodes(&self) -> usize {
-        self.seasons.iter().map(|season| season.episodes).sum()
+        self.counted_seasons().map(|season| season.episodes).sum()
     }
 
     #[inline]
@@ -268,12 +885,24 @@ Next episode: {}
             }
         }
         self.seasons.extend(iter.cloned());
+        self.update_current_season();
+        if self.poster.is_none() {
+            self.poster = other.poster.clone();
+        }
+        if self.source.is_none() {
+            self.source = other.source.clone();
+        }
+        match (&mut self.specials, &other.specials) {
+            (Some(specials), Some(other)) => specials.episodes = specials.episodes.max(other.episodes),
+            (None, Some(other)) => self.specials = Some(Season::new(other.episodes)),
+            _ => {}
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use io::BufWriter;
+    use io::{BufWriter, Write};
 
     use super::*;
 
@@ -332,6 +961,33 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_metadata() {
+        let content = "kind: miniseries
+media: /mnt/tv/Dark
+media: https://example.com/Dark.S01E02.mkv
+tags: sci-fi, with-partner
+state: paused
+poster: https://example.com/dark.jpg
+source: episodate:dark
+announced: S03E08
+last-watched: 2024-05-30T21:04:05Z
+watched-through: 2023-12-01
+future-key: some value
+10/20
+0/20
+";
+        let test: Serie = content.parse().unwrap();
+        assert_eq!(test.media, ["/mnt/tv/Dark", "https://example.com/Dark.S01E02.mkv"]);
+        assert_eq!(test.tags, ["sci-fi", "with-partner"]);
+        assert_eq!(test.state, Some(State::Paused));
+        assert_eq!(test.poster.as_deref(), Some("https://example.com/dark.jpg"));
+        assert_eq!(test.source.as_deref(), Some("episodate:dark"));
+        assert_eq!(test.announced, Some((3, 8)));
+        assert_eq!(test.total_episodes(), 40);
+        assert_eq!(test.to_string(), content);
+    }
+
     #[test]
     fn test_watch() {
         let mut test = get_test_serie();
@@ -384,6 +1040,7 @@ mod tests {
         test.unwatch(20);
         assert_eq!(test.next_episode_str().unwrap().as_str(), "S02E01");
         assert_eq!(test.total_watched(), 20);
+        assert_eq!(Serie::new(vec![], "Empty").unwatch(1), 1);
     }
 
 
@@ -423,6 +1080,182 @@ mod tests {
         assert_eq!(test.next_episode_str().unwrap(), expected);
     }
 
+    #[test]
+    fn test_seek() {
+        let mut test = get_test_serie();
+        assert!(test.seek(2, 5));
+        assert_eq!(test.next_episode_str().unwrap(), "S02E05");
+        assert_eq!(test.total_watched(), 24);
+        assert!(test.seek(1, 1));
+        assert_eq!(test.total_watched(), 0);
+        assert!(!test.seek(3, 1));
+        assert!(!test.seek(1, 21));
+        assert!(!test.seek(0, 1));
+        assert_eq!(test.next_episode_str().unwrap(), "S01E01");
+    }
+
+    #[test]
+    fn test_watch_until() {
+        let mut test = get_test_serie();
+        assert_eq!(test.watch_until(1, 20), WatchUntil::Watched);
+        assert_eq!(test.next_episode_str().unwrap(), "S02E01");
+        assert_eq!(test.watch_until(1, 12), WatchUntil::AlreadyPast);
+        assert_eq!(test.watch_until(3, 1), WatchUntil::NoSuchEpisode);
+        assert_eq!(test.watch_until(1, 21), WatchUntil::NoSuchEpisode);
+        assert_eq!(test.watch_until(2, 20), WatchUntil::Watched);
+        assert!(test.is_finished());
+        assert_eq!(test.watch_until(2, 20), WatchUntil::AlreadyPast);
+        let test: Serie = "specials: 0/2\n10/10\n0/10".parse().unwrap();
+        let mut test = test.with_specials(true);
+        assert_eq!(test.watch_until(2, 5), WatchUntil::Watched);
+        assert_eq!(test.to_string(), "specials: 0/2\n10/10\n5/10\n");
+        assert_eq!(test.next_episode_str().unwrap(), "S00E01");
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut test = get_test_serie();
+        assert_eq!(test.add_tag("anime"), Ok(true));
+        assert_eq!(test.add_tag("Anime"), Ok(false));
+        assert_eq!(test.add_tag("rewatch"), Ok(true));
+        assert!(test.add_tag("anime\nfoo").is_err());
+        assert!(test.add_tag("a,b").is_err());
+        assert!(test.add_tag(" ").is_err());
+        assert!(test.has_tag("ANIME"));
+        assert!(test.remove_tag("anime"));
+        assert!(!test.remove_tag("anime"));
+        assert_eq!(test.to_string(), "tags: rewatch\n10/20\n0/20\n");
+    }
+
+    #[test]
+    fn test_state() {
+        let mut test = get_test_serie();
+        test.name = "Dark".to_string();
+        assert_eq!(test.state(), State::Watching);
+        assert!(test.set_state(State::Paused));
+        assert!(!test.set_state(State::Paused));
+        assert_eq!(test.to_string(), "state: paused\n10/20\n0/20\n");
+        assert_eq!(test.normal(), "Dark S01E11 (paused)");
+        assert!(test.set_state(State::Watching));
+        assert_eq!(test.state, None);
+        test.set_state(State::PlanToWatch);
+        test.mark_watched();
+        assert_eq!(test.state(), State::Watching);
+        let finished: Serie = "3/3".parse().unwrap();
+        assert_eq!(finished.state(), State::Completed);
+        assert_eq!(finished.metadata("state"), ["completed"]);
+        let unknown: Serie = "state: binging\n3/3".parse().unwrap();
+        assert_eq!(unknown.metadata("state"), ["completed"]);
+        assert_eq!(unknown.to_string(), "state: binging\n3/3\n");
+    }
+
+    #[test]
+    fn test_rewatch() {
+        let mut test: Serie = "last-watched: 2024-05-30T21:04:05Z\n8/8\n3/3".parse().unwrap();
+        assert!(test.rewatch());
+        assert_eq!(test.to_string(), "last-watched: 2024-05-30T21:04:05Z\nwatched-through: 2024-05-30\n0/8\n0/3\n");
+        test.watch(3);
+        assert_eq!(test.next_episode_rewatch(), "Rewatch #1, S01E04");
+        assert_eq!(test.normal(), " Rewatch #1, S01E04");
+        assert!(!test.rewatch());
+        test.set_state(State::Completed);
+        assert!(test.rewatch());
+        assert_eq!(test.state, None);
+        assert_eq!(test.watched_through.len(), 2);
+        assert!(test.extended().contains("Next episode: Rewatch #2, S01E01\nState: watching\n"));
+    }
+
+    #[test]
+    fn test_rating_and_notes() {
+        let content = "rating: 9\nnotes: rewatch with: subtitles\n10/10 | 8 | the finale: wow\n2/10 | | slow\n";
+        let mut test: Serie = content.parse().unwrap();
+        assert_eq!(test.rating, Some(9));
+        assert_eq!(test.notes.as_deref(), Some("rewatch with: subtitles"));
+        assert_eq!(test.seasons()[0].notes.as_deref(), Some("the finale: wow"));
+        assert_eq!(test.to_string(), content);
+        assert!(test.extended().contains("Rating: 9/10\nNotes: rewatch with: subtitles\n"));
+        test.season_mut(2).unwrap().rating = Some(4);
+        assert_eq!(test.seasons()[1].to_string(), "2/10 | 4 | slow");
+        assert!(test.season_mut(0).is_none());
+        assert!(test.season_mut(3).is_none());
+        let invalid: Serie = "rating: 11\n1/2".parse().unwrap();
+        assert_eq!(invalid.rating, None);
+        assert_eq!(invalid.metadata("rating"), ["11"]);
+    }
+
+    #[test]
+    fn test_movie() {
+        let mut test: Serie = "kind: movie\nrating: 8\n0/1".parse().unwrap();
+        test.name = "Inception".to_string();
+        assert_eq!(test.kind(), Kind::Movie);
+        assert_eq!(test.normal(), "Inception UNWATCHED");
+        test.watch(1);
+        assert_eq!(test.normal(), "Inception WATCHED");
+        assert_eq!(test.extended(), "Name: Inception\nKind: movie\nWatched: yes\nState: completed\nRating: 8/10\n");
+        assert_eq!(test.metadata("kind"), ["movie"]);
+        assert_eq!(get_test_serie().metadata("kind"), ["series"]);
+    }
+
+    #[test]
+    fn test_specials() {
+        let content = "specials: 1/3\n8/8\n2/10\n";
+        let test: Serie = content.parse().unwrap();
+        assert_eq!(test.to_string(), content);
+        assert_eq!(test.next_episode_str().unwrap(), "S02E03");
+        assert_eq!(test.total_episodes(), 18);
+        let mut test = test.with_specials(true);
+        assert_eq!(test.next_episode_str().unwrap(), "S00E02");
+        assert_eq!((test.next_season(), test.next_episode()), (0, 2));
+        assert_eq!(test.total_episodes(), 21);
+        test.watch(3);
+        assert_eq!(test.to_string(), "specials: 3/3\n8/8\n3/10\n");
+        test.unwatch(2);
+        assert_eq!(test.to_string(), "specials: 3/3\n8/8\n1/10\n");
+        assert!(test.seek(0, 1));
+        assert!(!test.seek(0, 4));
+        assert_eq!(test.watch_until(0, 2), WatchUntil::Watched);
+        assert_eq!(test.watch_until(0, 1), WatchUntil::AlreadyPast);
+        assert_eq!(test.watch_until(0, 4), WatchUntil::NoSuchEpisode);
+        assert_eq!(test.specials.as_ref().map(|specials| specials.watched), Some(2));
+        assert!(test.extended().contains("\n0: 2/3\n1: 8/8\n"));
+        let mut finished: Serie = "specials: 0/1\n1/1".parse().unwrap();
+        assert!(finished.is_finished());
+        finished = finished.with_specials(true);
+        assert!(finished.is_not_finished());
+        assert_eq!(finished.next_episode_str().unwrap(), "S00E01");
+    }
+
+    #[test]
+    fn test_absolute_numbering() {
+        let mut test: Serie = "numbering: absolute\n61/61\n16/77\n0/30\n".parse().unwrap();
+        assert_eq!(test.absolute_of(2, 17), Some(78));
+        assert_eq!(test.absolute_of(1, 1), Some(1));
+        assert_eq!(test.absolute_of(2, 78), None);
+        assert_eq!(test.absolute_of(4, 1), None);
+        assert_eq!(test.episode_of_absolute(78), Some((2, 17)));
+        assert_eq!(test.episode_of_absolute(61), Some((1, 61)));
+        assert_eq!(test.episode_of_absolute(168), Some((3, 30)));
+        assert_eq!(test.episode_of_absolute(169), None);
+        assert_eq!(test.episode_of_absolute(0), None);
+        assert_eq!(test.next_episode_str().unwrap(), "078");
+        assert_eq!(test.find_episode("139"), Some((3, 1)));
+        assert_eq!(test.find_episode("S01E05"), Some((1, 5)));
+        assert_eq!(test.find_episode("pilot"), None);
+        assert_eq!(get_test_serie().find_episode("5"), None);
+        test.watch(91);
+        assert_eq!(test.next_episode_str(), None);
+        assert_eq!(test.metadata("numbering"), ["absolute"]);
+        assert_eq!(get_test_serie().metadata("numbering"), ["seasons"]);
+    }
+
+    #[test]
+    fn test_parse_episode() {
+        assert_eq!(parse_episode("S02E05"), Some((2, 5)));
+        assert_eq!(parse_episode("s1e12"), Some((1, 12)));
+        assert_eq!(parse_episode("2x05"), None);
+        assert_eq!(parse_episode("S02"), None);
+    }
+
     #[test]
     fn test_finished() {
         let mut test: Serie = "10/20\n0/20".parse().unwrap();
@@ -447,4 +1280,12 @@ mod tests {
         let expected: Serie = "10/20\n2/22".parse().unwrap();
         assert_eq!(test.seasons, expected.seasons)
     }
+
+    #[test]
+    fn test_merge_series_finished() {
+        let mut test: Serie = "20/20".parse().unwrap();
+        assert!(test.is_finished());
+        test.merge_serie(&"0/20\n0/10".parse().unwrap());
+        assert_eq!(test.next_episode_str().unwrap(), "S02E01");
+    }
 }
@@ -1,10 +1,21 @@
 use core::fmt;
 use std::str::FromStr;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
 pub struct Season {
     pub episodes: usize,
     pub watched: usize,
+    /// From 1 to 10
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rating: Option<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Parses a rating from 1 to 10
+pub fn parse_rating(s: &str) -> Option<u8> {
+    s.trim().parse().ok().filter(|rating| (1..=10).contains(rating))
 }
 
 pub enum SeasonError {
@@ -18,14 +29,27 @@ impl FromStr for Season {
         if s.is_empty() {
             return Err(SeasonError::EmptySeason)
         }
-        let sep_index = s.chars().position(|c| c == '/');
+        // like "10/10 | 8 | great finale", the rating and notes being optional
+        let mut parts = s.splitn(3, '|');
+        let progress = parts.next().unwrap_or_default().trim();
+        let rating = parts.next().map(str::trim).filter(|rating| !rating.is_empty());
+        let notes = parts.next().map(str::trim).filter(|notes| !notes.is_empty());
+        // a rating that isn't one, like "good" or "11", is kept as part of the notes
+        let (rating, notes) = match rating.map(|rating| (rating, parse_rating(rating))) {
+            Some((_, Some(rating))) => (Some(rating), notes.map(str::to_string)),
+            Some((invalid, None)) => (None, Some(notes.map_or(invalid.to_string(), |notes| format!("{invalid} | {notes}")))),
+            None => (None, notes.map(str::to_string)),
+        };
+        let sep_index = progress.chars().position(|c| c == '/');
         if let Some(index) = sep_index {
-            let watched = s[..index].parse();
-            let episodes = s[index+1..].parse();
+            let watched = progress[..index].parse();
+            let episodes = progress[index+1..].parse();
             if let (Ok(watched), Ok(episodes)) = (watched, episodes) {
                 return Ok(Self {
                     episodes,
                     watched,
+                    rating,
+                    notes,
                 })
             }
         }
@@ -35,7 +59,13 @@ impl FromStr for Season {
 
 impl fmt::Display for Season {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}/{}", self.watched, self.episodes)
+        write!(f, "{}/{}", self.watched, self.episodes)?;
+        match (self.rating, &self.notes) {
+            (None, None) => Ok(()),
+            (Some(rating), None) => write!(f, " | {rating}"),
+            (Some(rating), Some(notes)) => write!(f, " | {rating} | {notes}"),
+            (None, Some(notes)) => write!(f, " | | {notes}"),
+        }
     }
 }
 
@@ -77,3 +107,29 @@ impl Season {
         count - unwatch_count
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn season(s: &str) -> Option<Season> {
+        s.parse().ok()
+    }
+
+    #[test]
+    fn test_rating_and_notes() {
+        let rated = season("10/10 | 8 | great finale | really").unwrap();
+        assert_eq!(rated.rating, Some(8));
+        assert_eq!(rated.notes.as_deref(), Some("great finale | really"));
+        assert_eq!(rated.to_string(), "10/10 | 8 | great finale | really");
+        let noted = season("3/10 | | slow start").unwrap();
+        assert_eq!(noted.rating, None);
+        assert_eq!(noted.to_string(), "3/10 | | slow start");
+        assert_eq!(season("3/10 | 7").unwrap().to_string(), "3/10 | 7");
+        assert_eq!(season("3/10 | 11").unwrap().to_string(), "3/10 | | 11");
+        let unrated = season("3/10 | good | slow start").unwrap();
+        assert_eq!(unrated.rating, None);
+        assert_eq!(unrated.notes.as_deref(), Some("good | slow start"));
+        assert!(season("3/ten | 8").is_none());
+    }
+}
@@ -0,0 +1,120 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+use crate::serie::{Serie, State};
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum Format {
+    /// A single line, for polybar, i3blocks and the like
+    #[value(alias = "p")]
+    Plain,
+    /// The json of waybar's custom modules
+    #[value(alias = "w")]
+    Waybar,
+}
+
+/// A summary of the progress over a set of series
+#[derive(Debug, Default, PartialEq)]
+pub struct Status {
+    /// Names and next episodes of the unfinished series
+    pub in_progress: Vec<(String, String)>,
+    pub finished: usize,
+    pub episodes_left: usize,
+    pub watched: usize,
+    pub episodes: usize,
+}
+
+impl Status {
+    /// Paused and dropped series are left out, as they're neither being watched nor finished
+    pub fn from_series(series: impl Iterator<Item = Serie>) -> Self {
+        let mut status = Self::default();
+        for serie in series.filter(|serie| !matches!(serie.state(), State::Paused | State::Dropped)) {
+            status.watched += serie.total_watched();
+            status.episodes += serie.total_episodes();
+            match serie.next_episode_str() {
+                Some(_) if serie.is_not_finished() => {
+                    status.episodes_left += serie.total_episodes().saturating_sub(serie.total_watched());
+                    let next_episode = serie.next_episode_rewatch();
+                    status.in_progress.push((serie.name, next_episode));
+                }
+                _ => status.finished += 1,
+            }
+        }
+        status.in_progress.sort();
+        status
+    }
+
+    /// `watching` while any serie is unfinished, `finished` once all are, and `empty` without series
+    pub fn class(&self) -> &'static str {
+        if !self.in_progress.is_empty() {
+            "watching"
+        } else if self.finished > 0 {
+            "finished"
+        } else {
+            "empty"
+        }
+    }
+
+    pub fn plain(&self) -> String {
+        match self.class() {
+            "watching" => format!("{} watching, {} episodes left", self.in_progress.len(), self.episodes_left),
+            "finished" => format!("{} series, all finished", self.finished),
+            _ => "no series".to_string(),
+        }
+    }
+
+    pub fn tooltip(&self) -> String {
+        self.in_progress
+            .iter()
+            .map(|(name, next_episode)| format!("{name} {next_episode}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn waybar(&self) -> Value {
+        let percentage = (self.watched * 100).checked_div(self.episodes).unwrap_or(0);
+        json!({
+            "text": self.plain(),
+            "tooltip": self.tooltip(),
+            "class": self.class(),
+            "percentage": percentage,
+        })
+    }
+
+    pub fn format(&self, format: &Format) -> String {
+        match format {
+            Format::Plain => self.plain(),
+            Format::Waybar => self.waybar().to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::tests::serie;
+
+    #[test]
+    fn test_status() {
+        let series = [serie("8/8\n1/8", "Dark"), serie("3/3", "Chernobyl"), serie("watched-through: 2024-05-30\n0/9", "Arcane")];
+        let status = Status::from_series(series.into_iter());
+        assert_eq!(status.plain(), "2 watching, 16 episodes left");
+        assert_eq!(status.tooltip(), "Arcane Rewatch #1, S01E01\nDark S02E02");
+        let waybar = status.waybar();
+        assert_eq!(waybar["class"], "watching");
+        assert_eq!(waybar["percentage"], 42);
+    }
+
+    #[test]
+    fn test_status_finished() {
+        let status = Status::from_series([serie("3/3", "Chernobyl")].into_iter());
+        assert_eq!(status.class(), "finished");
+        assert_eq!(status.plain(), "1 series, all finished");
+        let paused: Serie = "state: paused\n1/8".parse().unwrap();
+        let status = Status::from_series([paused].into_iter());
+        assert_eq!(status.class(), "empty");
+        let status = Status::from_series(std::iter::empty());
+        assert_eq!(status.class(), "empty");
+        assert_eq!(status.waybar()["percentage"], 0);
+    }
+}
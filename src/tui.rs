@@ -0,0 +1,398 @@
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::{Block, Clear, List, ListItem, ListState, Paragraph},
+    DefaultTerminal, Frame,
+};
+use std::{fs, io, path::{Path, PathBuf}, time::Duration};
+
+use crate::{
+    cli::Include,
+    episodate::{self, TvShow},
+    lock::{DirLock, LockError},
+    serie::{self, Numbering, Serie},
+    utils,
+};
+
+const HELP: &str = "q quit | j/k move | w watch | u unwatch | s seek | d delete | i include | H hidden | / search";
+const PROGRESS_WIDTH: usize = 20;
+
+enum Mode {
+    Normal,
+    Seek(String),
+    Delete,
+    Search(String),
+    Results(Vec<TvShow>, ListState),
+}
+
+/// Interactive terminal ui over the series of some directories
+pub struct Tui {
+    directories: Vec<PathBuf>,
+    series: Vec<(Serie, PathBuf)>,
+    // indexes of series that pass the filters
+    visible: Vec<usize>,
+    include: Include,
+    hidden: bool,
+    lock_timeout: Duration,
+    dry_run: bool,
+    list: ListState,
+    mode: Mode,
+    status: String,
+}
+
+fn progress_bar(percentage: f32, width: usize) -> String {
+    let filled = if percentage.is_nan() {
+        0
+    } else {
+        ((percentage / 100. * width as f32).round() as usize).min(width)
+    };
+    "█".repeat(filled) + &"░".repeat(width - filled)
+}
+
+fn popup_area(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
+    let [area] = Layout::vertical([Constraint::Percentage(percent_y)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::horizontal([Constraint::Percentage(percent_x)])
+        .flex(Flex::Center)
+        .areas(area);
+    area
+}
+
+impl Tui {
+    pub fn new(
+        directories: Vec<PathBuf>,
+        include: Include,
+        hidden: bool,
+        lock_timeout: Duration,
+        dry_run: bool,
+    ) -> Self {
+        let mut tui = Self {
+            directories,
+            series: vec![],
+            visible: vec![],
+            include,
+            hidden,
+            lock_timeout,
+            dry_run,
+            list: ListState::default(),
+            mode: Mode::Normal,
+            status: String::new(),
+        };
+        tui.reload();
+        tui
+    }
+
+    pub fn run(mut self) -> io::Result<()> {
+        let mut terminal = ratatui::init();
+        let result = self.event_loop(&mut terminal);
+        ratatui::restore();
+        result
+    }
+
+    fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle_key(key.code, terminal)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn reload(&mut self) {
+        self.series = utils::series_dirs_reader(&self.directories)
+            .map(|series| series.collect())
+            .unwrap_or_default();
+        self.series.sort_by(|a, b| a.0.name.cmp(&b.0.name));
+        self.filter();
+    }
+
+    fn filter(&mut self) {
+        self.visible = self
+            .series
+            .iter()
+            .enumerate()
+            .filter(|(_, (serie, path))| {
+                (self.hidden || !utils::is_hidden(path)) && self.include.matches(serie)
+            })
+            .map(|(i, _)| i)
+            .collect();
+        let selected = self.list.selected().unwrap_or(0);
+        self.list.select(if self.visible.is_empty() {
+            None
+        } else {
+            Some(selected.min(self.visible.len() - 1))
+        });
+    }
+
+    fn selected(&self) -> Option<&(Serie, PathBuf)> {
+        let index = *self.visible.get(self.list.selected()?)?;
+        self.series.get(index)
+    }
+
+    /// Locks the directory of `path`, unless it's a dry run, which writes nothing
+    fn lock(&self, path: &Path) -> Result<Option<DirLock>, LockError> {
+        if self.dry_run {
+            return Ok(None);
+        }
+        DirLock::acquire(path, self.lock_timeout, |_| {}).map(Some)
+    }
+
+    /// Applies `change` to the selected serie under the directory lock, reading it again first
+    fn modify_selected(&mut self, change: impl FnOnce(&mut Serie) -> Result<(), String>) {
+        let Some(index) = self.list.selected().and_then(|i| self.visible.get(i).copied()) else {
+            return;
+        };
+        let path = self.series[index].1.clone();
+        let _lock = match self.lock(&path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        let Some(mut serie) = Serie::from_file(&path) else {
+            self.status = format!("{} is gone or malformed", path.to_str().unwrap());
+            self.reload();
+            return;
+        };
+        if let Err(e) = change(&mut serie) {
+            self.status = e;
+            return;
+        }
+        if !self.dry_run {
+            if let Err(e) = serie.write(&path) {
+                self.status = format!("Couldn't write {}: {e}", path.to_str().unwrap());
+                return;
+            }
+        }
+        self.status = format!("{} {}", serie.name, serie.next_episode_rewatch());
+        self.series[index].0 = serie;
+        self.filter();
+    }
+
+    fn delete_selected(&mut self) {
+        let Some((serie, path)) = self.selected() else {
+            return;
+        };
+        let (name, path) = (serie.name.clone(), path.clone());
+        let _lock = match self.lock(&path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        self.status = if self.dry_run {
+            format!("Deleted {name} (dry-run)")
+        } else if let Err(e) = fs::remove_file(&path) {
+            format!("Couldn't delete {}: {e}", path.to_str().unwrap())
+        } else {
+            format!("Deleted {name}")
+        };
+        self.reload();
+    }
+
+    /// Adds the online show with `permalink`, or updates it if it already exists
+    fn add_online(&mut self, permalink: &str) {
+        let serie = match episodate::fetch_detail(permalink) {
+            Ok(serie) => serie,
+            Err(e) => {
+                self.status = e;
+                return;
+            }
+        };
+        let path = self.directories[0].join(serie.filename());
+        let _lock = match self.lock(&path) {
+            Ok(lock) => lock,
+            Err(e) => {
+                self.status = e.to_string();
+                return;
+            }
+        };
+        let (serie, verb) = match Serie::from_file(&path) {
+            Some(mut old_serie) => {
+                old_serie.merge_serie(&serie);
+                (old_serie, "Updated")
+            }
+            None => (serie, "Added"),
+        };
+        self.status = if self.dry_run {
+            format!("{verb} {} (dry-run)", serie.name)
+        } else if let Err(e) = serie.write(&path) {
+            format!("Couldn't write {}: {e}", path.to_str().unwrap())
+        } else {
+            format!("{verb} {}", serie.name)
+        };
+        self.reload();
+    }
+
+    /// Returns false when the ui should quit
+    fn handle_key(&mut self, code: KeyCode, terminal: &mut DefaultTerminal) -> io::Result<bool> {
+        match &mut self.mode {
+            Mode::Normal => match code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Char('j') | KeyCode::Down => self.list.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => self.list.select_previous(),
+                KeyCode::Char('g') | KeyCode::Home => self.list.select_first(),
+                KeyCode::Char('G') | KeyCode::End => self.list.select_last(),
+                KeyCode::Char('w') => self.modify_selected(|serie| {
+                    serie.watch(1);
+                    serie.mark_watched();
+                    Ok(())
+                }),
+                KeyCode::Char('u') => self.modify_selected(|serie| {
+                    serie.unwatch(1);
+                    Ok(())
+                }),
+                KeyCode::Char('s') if self.selected().is_some() => self.mode = Mode::Seek(String::new()),
+                KeyCode::Char('d') if self.selected().is_some() => self.mode = Mode::Delete,
+                KeyCode::Char('i') => {
+                    self.include = self.include.next();
+                    self.filter();
+                }
+                KeyCode::Char('H') => {
+                    self.hidden = !self.hidden;
+                    self.filter();
+                }
+                KeyCode::Char('r') => self.reload(),
+                KeyCode::Char('/') => self.mode = Mode::Search(String::new()),
+                _ => {}
+            },
+            Mode::Seek(input) | Mode::Search(input) => match code {
+                KeyCode::Esc => self.mode = Mode::Normal,
+                KeyCode::Backspace => {
+                    input.pop();
+                }
+                KeyCode::Char(c) => input.push(c),
+                KeyCode::Enter => {
+                    let input = std::mem::take(input);
+                    let mode = std::mem::replace(&mut self.mode, Mode::Normal);
+                    if let Mode::Seek(_) = mode {
+                        self.modify_selected(|serie| {
+                            match serie.find_episode(&input) {
+                                Some((season, episode)) if serie.seek(season, episode) => Ok(()),
+                                _ if serie::parse_episode(&input).is_none() && serie.numbering() != Numbering::Absolute => {
+                                    Err(format!("\"{input}\" is not like S01E05"))
+                                }
+                                _ => Err(format!("{} has no episode {input}", serie.name)),
+                            }
+                        });
+                    } else {
+                        self.status = format!("Searching \"{input}\"...");
+                        terminal.draw(|frame| self.draw(frame))?;
+                        let shows = episodate::search(&input);
+                        self.status = format!("{} results for \"{input}\"", shows.len());
+                        if !shows.is_empty() {
+                            self.mode = Mode::Results(shows, ListState::default().with_selected(Some(0)));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            Mode::Delete => {
+                if code == KeyCode::Char('y') {
+                    self.delete_selected();
+                }
+                self.mode = Mode::Normal;
+            }
+            Mode::Results(shows, state) => match code {
+                KeyCode::Esc | KeyCode::Char('q') => self.mode = Mode::Normal,
+                KeyCode::Char('j') | KeyCode::Down => state.select_next(),
+                KeyCode::Char('k') | KeyCode::Up => state.select_previous(),
+                KeyCode::Enter => {
+                    if let Some(show) = state.selected().and_then(|i| shows.get(i)) {
+                        let permalink = show.permalink().to_string();
+                        self.status = format!("Adding {permalink}...");
+                        terminal.draw(|frame| self.draw(frame))?;
+                        self.add_online(&permalink);
+                    }
+                    self.mode = Mode::Normal;
+                }
+                _ => {}
+            },
+        }
+        Ok(true)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] = Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, detail_area] =
+            Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main);
+
+        let width = self
+            .visible
+            .iter()
+            .map(|&i| self.series[i].0.name.chars().count())
+            .max()
+            .unwrap_or(0);
+        let items: Vec<ListItem> = self
+            .visible
+            .iter()
+            .map(|&i| {
+                let serie = &self.series[i].0;
+                let percentage = serie.watched_percentage();
+                ListItem::new(format!(
+                    "{:<width$} {} {:>3.0}% {}",
+                    serie.name,
+                    progress_bar(percentage, PROGRESS_WIDTH),
+                    if percentage.is_nan() { 0. } else { percentage },
+                    serie.next_episode_rewatch(),
+                ))
+            })
+            .collect();
+        let title = format!(
+            " series ({:?}{}) ",
+            self.include,
+            if self.hidden { ", hidden" } else { "" }
+        );
+        let list = List::new(items)
+            .block(Block::bordered().title(title))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, list_area, &mut self.list);
+
+        let detail = self.selected().map(|(serie, _)| serie.extended()).unwrap_or_default();
+        frame.render_widget(Paragraph::new(detail).block(Block::bordered().title(" detail ")), detail_area);
+
+        let status_line = match &self.mode {
+            Mode::Seek(input) => format!("seek to (S01E05, or 1071 when numbered absolutely): {input}"),
+            Mode::Search(input) => format!("search episodate: {input}"),
+            Mode::Delete => format!(
+                "delete \"{}\"? [y/N]{}",
+                self.selected().map_or("", |(serie, _)| &serie.name),
+                if self.dry_run { " (dry-run)" } else { "" }
+            ),
+            _ if self.status.is_empty() => HELP.to_string(),
+            _ => self.status.clone(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+
+        if let Mode::Results(shows, state) = &mut self.mode {
+            let area = popup_area(frame.area(), 60, 60);
+            let items: Vec<ListItem> = shows
+                .iter()
+                .map(|show| ListItem::new(format!("{} ({})", show.permalink(), show.name())))
+                .collect();
+            let list = List::new(items)
+                .block(Block::bordered().title(" add from episodate (enter to add, esc to cancel) "))
+                .highlight_style(Style::new().reversed());
+            frame.render_widget(Clear, area);
+            frame.render_stateful_widget(list, area, state);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bar() {
+        assert_eq!(progress_bar(25., 4), "█░░░");
+        assert_eq!(progress_bar(100., 4), "████");
+        assert_eq!(progress_bar(f32::NAN, 2), "░░");
+    }
+}
@@ -3,7 +3,7 @@ use home::home_dir;
 use clap::Command;
 use clap_complete::{generate, Generator};
 use std::{
-    fs, io::{self}, iter::FlatMap, path::{Path, PathBuf}
+    fs::{self, File}, io::{self, Write}, path::{Path, PathBuf}, time::{SystemTime, UNIX_EPOCH}
 };
 
 #[inline(always)]
@@ -15,17 +15,235 @@ pub fn append_home_dir(strs: &[&str]) -> PathBuf {
     out
 }
 
-pub fn series_dir_reader(dir: &Path) 
--> io::Result<FlatMap<fs::ReadDir, Option<(Serie, PathBuf)>, impl FnMut(Result<fs::DirEntry, io::Error>) -> Option<(Serie, PathBuf)>>>
-{
+#[inline(always)]
+fn xdg_dir(var: &str, fallback: &[&str]) -> PathBuf {
+    match std::env::var_os(var) {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("bingewatcher"),
+        _ => append_home_dir(fallback),
+    }
+}
+
+/// `$XDG_DATA_HOME/bingewatcher`, falling back to `~/.local/share/bingewatcher`
+#[inline(always)]
+pub fn default_directory() -> PathBuf {
+    xdg_dir("XDG_DATA_HOME", &[".local", "share", "bingewatcher"])
+}
+
+/// Where series were stored before following the XDG base directories
+#[inline(always)]
+pub fn legacy_directory() -> PathBuf {
+    append_home_dir(&[".cache", "bingewatcher"])
+}
+
+/// `$XDG_CONFIG_HOME/bingewatcher`, falling back to `~/.config/bingewatcher`
+#[inline(always)]
+pub fn config_dir() -> PathBuf {
+    xdg_dir("XDG_CONFIG_HOME", &[".config", "bingewatcher"])
+}
+
+/// What [`migrate_directory`] did
+#[derive(Debug, PartialEq)]
+pub enum Migration {
+    /// There was nothing to move
+    Nothing,
+    Moved,
+    /// Both directories have files, so neither was touched
+    BothHaveFiles,
+}
+
+/// Moves the files of `old` into `new`, if `old` exists and `new` has no files yet
+pub fn migrate_directory(old: &Path, new: &Path) -> io::Result<Migration> {
+    if !fs::read_dir(old).is_ok_and(|mut dir| dir.next().is_some()) {
+        return Ok(Migration::Nothing);
+    }
+    if fs::read_dir(new).is_ok_and(|mut dir| dir.next().is_some()) {
+        return Ok(Migration::BothHaveFiles);
+    }
+    if let Some(parent) = new.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let _ = fs::remove_dir(new);
+    if fs::rename(old, new).is_err() {
+        // old and new may be on different filesystems
+        move_files(old, new)?;
+    }
+    Ok(Migration::Moved)
+}
+
+/// Copies the files of `old` into `new`, then removes them and `old`. Refuses to touch anything
+/// when `old` has entries other than files, like directories, so nothing gets lost
+fn move_files(old: &Path, new: &Path) -> io::Result<()> {
+    let paths = fs::read_dir(old)?.map(|entry| entry.map(|entry| entry.path())).collect::<io::Result<Vec<_>>>()?;
+    if let Some(path) = paths.iter().find(|path| !path.is_file()) {
+        return Err(io::Error::other(format!(
+            "{} isn't a file. Move the directory by hand",
+            path.to_str().unwrap()
+        )));
+    }
+    fs::create_dir_all(new)?;
+    for path in &paths {
+        fs::copy(path, new.join(path.file_name().unwrap()))?;
+    }
+    for path in &paths {
+        fs::remove_file(path)?;
+    }
+    fs::remove_dir(old)
+}
+
+/// Date of `days` since the unix epoch, like `2024-05-30`
+fn date_of(days: i64) -> String {
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+#[inline(always)]
+fn unix_seconds() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Today's date in UTC, like `2024-05-30`
+pub fn today() -> String {
+    date_of((unix_seconds() / 86_400) as i64)
+}
+
+/// The current time in UTC, like `2024-05-30T21:04:05Z`
+pub fn now() -> String {
+    let seconds = unix_seconds();
+    let time = seconds % 86_400;
+    format!("{}T{:02}:{:02}:{:02}Z", date_of((seconds / 86_400) as i64), time / 3600, time / 60 % 60, time % 60)
+}
+
+/// Writes `contents` to a temporary file next to `path`, syncs it and renames it over `path`
+pub fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path.file_name().ok_or(io::ErrorKind::InvalidInput)?;
+    let mut tmp_name = std::ffi::OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".tmp");
+    let tmp = path.with_file_name(tmp_name);
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+        return result;
+    }
+    // make the rename itself durable
+    if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Series whose file name starts with a dot are hidden, unless asked for
+#[inline(always)]
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'))
+}
+
+#[inline(always)]
+fn is_temporary(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "tmp")
+}
+
+pub fn series_dir_reader(dir: &Path) -> io::Result<impl Iterator<Item = (Serie, PathBuf)>> {
     let _ = std::fs::create_dir_all(dir);
     let dir = fs::read_dir(dir)?;
     Ok(dir.flat_map(|entry| {
         let path = entry.expect("File error").path();
+        if is_temporary(&path) {
+            return None;
+        }
         Serie::from_file(&path).map(|serie| (serie,path))
     }))
 }
 
+pub fn series_dirs_reader(dirs: &[PathBuf]) -> io::Result<impl Iterator<Item = (Serie, PathBuf)>> {
+    let readers = dirs
+        .iter()
+        .map(|dir| series_dir_reader(dir))
+        .collect::<io::Result<Vec<_>>>()?;
+    Ok(readers.into_iter().flatten())
+}
+
+/// Whether `name` can be the file name of a serie, staying in the series directory
+pub fn is_file_name(name: &str) -> bool {
+    !(name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\', '\0']))
+}
+
+/// Finds the serie named `name`: the one with the same name (ignoring case), or the only one
+/// whose name contains it
+pub fn find_serie(series: impl Iterator<Item = (Serie, PathBuf)>, name: &str) -> Option<(Serie, PathBuf)> {
+    let mut candidates: Vec<_> = series.filter(|(serie, _)| serie.matches(name)).collect();
+    if let Some(index) = candidates.iter().position(|(serie, _)| serie.name.eq_ignore_ascii_case(name)) {
+        return Some(candidates.swap_remove(index));
+    }
+    if candidates.len() == 1 {
+        candidates.pop()
+    } else {
+        None
+    }
+}
+
+/// Finds the serie named exactly `name`, ignoring case. For changes, where guessing wrong would
+/// change another serie
+pub fn find_serie_exact(mut series: impl Iterator<Item = (Serie, PathBuf)>, name: &str) -> Option<(Serie, PathBuf)> {
+    series.find(|(serie, _)| serie.name.eq_ignore_ascii_case(name))
+}
+
+/// Splits a command into its words like a shell, so `mpv --title='My Show'` passes `My Show`
+/// as one word. Single and double quotes group words, and a backslash escapes the next character
+/// (but not within single quotes). Returns None when a quote isn't closed
+pub fn split_command(command: &str) -> Option<Vec<String>> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => word.push(chars.next()?),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => word.get_or_insert_with(String::new).push(chars.next()?),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Some(words)
+}
+
 // pub fn series_paths_reader<'a>(dir: &'a[PathBuf]) -> FlatMap<std::slice::Iter<'a, PathBuf>, Option<Serie>, impl FnMut(&PathBuf) -> Option<Serie>>
 // {
 //     // let _ = std::fs::create_dir_all(dir);
@@ -36,3 +254,109 @@ pub fn series_dir_reader(dir: &Path)
 pub fn print_completions<G: Generator>(gen: G, cmd: &mut Command) {
     generate(gen, cmd, cmd.get_name().to_string(), &mut io::stdout());
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// A fresh path in the temp directory, unique to `name` and this test run
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bw-test-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// The serie `name` with `content`, like in its file
+    pub(crate) fn serie(content: &str, name: &str) -> Serie {
+        let mut serie: Serie = content.parse().unwrap();
+        serie.name = name.to_string();
+        serie
+    }
+
+    #[test]
+    fn test_migrate_directory() {
+        let root = temp_dir("migrate");
+        let (old, new) = (root.join("cache"), root.join("share").join("bingewatcher"));
+        fs::create_dir_all(&old).unwrap();
+        fs::write(old.join("Dark.bw"), "1/10\n").unwrap();
+        assert_eq!(migrate_directory(&old, &new).unwrap(), Migration::Moved);
+        assert!(!old.exists());
+        assert_eq!(fs::read_to_string(new.join("Dark.bw")).unwrap(), "1/10\n");
+        // nothing left to migrate
+        assert_eq!(migrate_directory(&old, &new).unwrap(), Migration::Nothing);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_date_of() {
+        assert_eq!(date_of(0), "1970-01-01");
+        assert_eq!(date_of(19_783), "2024-03-01");
+        assert_eq!(date_of(11_016), "2000-02-29");
+    }
+
+    #[test]
+    fn test_find_serie() {
+        let series = || {
+            ["Dark", "Dark Matter", "Lost"].into_iter().map(|name| (serie("0/10", name), PathBuf::new()))
+        };
+        assert_eq!(find_serie(series(), "dark").unwrap().0.name, "Dark");
+        assert_eq!(find_serie(series(), "matter").unwrap().0.name, "Dark Matter");
+        assert_eq!(find_serie(series(), "los").unwrap().0.name, "Lost");
+        assert!(find_serie(series(), "ar").is_none());
+        assert!(find_serie(series(), "Fargo").is_none());
+        assert_eq!(find_serie_exact(series(), "dark matter").unwrap().0.name, "Dark Matter");
+        assert!(find_serie_exact(series(), "matter").is_none());
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(split_command("  mpv  --fs ").unwrap(), ["mpv", "--fs"]);
+        assert_eq!(split_command("mpv --title='My Show' \"a \\\"b\\\"\" c\\ d ''").unwrap(), ["mpv", "--title=My Show", "a \"b\"", "c d", ""]);
+        assert_eq!(split_command("").unwrap(), Vec::<String>::new());
+        assert!(split_command("mpv 'unclosed").is_none());
+        assert!(split_command("mpv \\").is_none());
+    }
+
+    #[test]
+    fn test_write_atomic() {
+        let root = temp_dir("write-atomic");
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("Dark.bw");
+        fs::write(&path, "1/10\n").unwrap();
+        write_atomic(&path, b"2/10\n").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "2/10\n");
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+        assert!(write_atomic(&root.join("missing").join("Dark.bw"), b"").is_err());
+        assert_eq!(fs::read_dir(&root).unwrap().count(), 1);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_migrate_directory_not_empty() {
+        let root = temp_dir("migrate-not-empty");
+        let (old, new) = (root.join("cache"), root.join("share"));
+        fs::create_dir_all(&old).unwrap();
+        fs::create_dir_all(&new).unwrap();
+        fs::write(old.join("Dark.bw"), "1/10\n").unwrap();
+        fs::write(new.join("Lost.bw"), "1/10\n").unwrap();
+        assert_eq!(migrate_directory(&old, &new).unwrap(), Migration::BothHaveFiles);
+        assert!(old.join("Dark.bw").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_move_files() {
+        let root = temp_dir("move-files");
+        let (old, new) = (root.join("cache"), root.join("share"));
+        fs::create_dir_all(old.join("backups")).unwrap();
+        fs::write(old.join("Dark.bw"), "1/10\n").unwrap();
+        assert!(move_files(&old, &new).is_err());
+        assert!(old.join("Dark.bw").exists());
+        assert!(old.join("backups").exists());
+        fs::remove_dir(old.join("backups")).unwrap();
+        move_files(&old, &new).unwrap();
+        assert!(!old.exists());
+        assert_eq!(fs::read_to_string(new.join("Dark.bw")).unwrap(), "1/10\n");
+        fs::remove_dir_all(root).unwrap();
+    }
+}
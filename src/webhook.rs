@@ -0,0 +1,192 @@
+use serde_json::Value;
+use std::fmt;
+use tiny_http::{Header, Response, Server};
+
+use crate::api;
+
+/// An episode a media server finished playing
+#[derive(Debug, PartialEq)]
+pub struct PlaybackEvent {
+    pub show: String,
+    pub season: usize,
+    pub episode: usize,
+}
+
+#[derive(Debug)]
+pub enum WebhookError {
+    MalformedPayload,
+    UnknownFormat,
+}
+
+impl fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookError::MalformedPayload => write!(f, "Payload is not json"),
+            WebhookError::UnknownFormat => write!(f, "Payload is not a Jellyfin, Plex or Kodi event"),
+        }
+    }
+}
+
+#[inline(always)]
+fn number(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(number) => number.as_u64().map(|n| n as usize),
+        Value::String(string) => string.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Jellyfin's webhook plugin, with its default template
+fn parse_jellyfin(json: &Value) -> Option<Option<PlaybackEvent>> {
+    let notification = json.get("NotificationType")?.as_str()?;
+    let finished = notification == "PlaybackStop"
+        && json.get("PlayedToCompletion").and_then(Value::as_bool) == Some(true)
+        && json.get("ItemType").and_then(Value::as_str) == Some("Episode");
+    if !finished {
+        return Some(None);
+    }
+    Some(Some(PlaybackEvent {
+        show: json.get("SeriesName")?.as_str()?.to_string(),
+        season: number(json.get("SeasonNumber")?)?,
+        episode: number(json.get("EpisodeNumber")?)?,
+    }))
+}
+
+/// Plex webhooks. Plex sends `media.scrobble` once an episode is mostly played
+fn parse_plex(json: &Value) -> Option<Option<PlaybackEvent>> {
+    let event = json.get("event")?.as_str()?;
+    let metadata = json.get("Metadata")?;
+    if event != "media.scrobble" || metadata.get("type").and_then(Value::as_str) != Some("episode") {
+        return Some(None);
+    }
+    Some(Some(PlaybackEvent {
+        show: metadata.get("grandparentTitle")?.as_str()?.to_string(),
+        season: number(metadata.get("parentIndex")?)?,
+        episode: number(metadata.get("index")?)?,
+    }))
+}
+
+/// Kodi's `Player.OnStop` json-rpc notification
+fn parse_kodi(json: &Value) -> Option<Option<PlaybackEvent>> {
+    let method = json.get("method")?.as_str()?;
+    let data = json.get("params")?.get("data")?;
+    let item = data.get("item")?;
+    let finished = method == "Player.OnStop"
+        && data.get("end").and_then(Value::as_bool) == Some(true)
+        && item.get("type").and_then(Value::as_str) == Some("episode");
+    if !finished {
+        return Some(None);
+    }
+    Some(Some(PlaybackEvent {
+        show: item.get("showtitle")?.as_str()?.to_string(),
+        season: number(item.get("season")?)?,
+        episode: number(item.get("episode")?)?,
+    }))
+}
+
+/// The `payload` part of a multipart/form-data body, which is how Plex sends its json
+fn multipart_payload<'a>(body: &'a [u8], content_type: &str) -> Option<&'a [u8]> {
+    let boundary = content_type.split("boundary=").nth(1)?.trim_matches('"');
+    let delimiter = format!("--{boundary}");
+    let body = match std::str::from_utf8(body) {
+        Ok(body) => body,
+        // the thumbnail part isn't utf-8; the payload comes before it
+        Err(e) => std::str::from_utf8(&body[..e.valid_up_to()]).ok()?,
+    };
+    body.split(delimiter.as_str())
+        .find(|part| part.contains("name=\"payload\""))
+        .and_then(|part| part.split_once("\r\n\r\n"))
+        .map(|(_, content)| content.trim_end_matches("\r\n").as_bytes())
+}
+
+/// Reads a playback event from a webhook request. `Ok(None)` is a valid event that isn't about
+/// finishing an episode
+pub fn parse(body: &[u8], content_type: Option<&str>) -> Result<Option<PlaybackEvent>, WebhookError> {
+    let body = match content_type {
+        Some(content_type) if content_type.starts_with("multipart/form-data") => {
+            multipart_payload(body, content_type).ok_or(WebhookError::MalformedPayload)?
+        }
+        _ => body,
+    };
+    let json: Value = serde_json::from_slice(body).map_err(|_| WebhookError::MalformedPayload)?;
+    parse_jellyfin(&json)
+        .or_else(|| parse_plex(&json))
+        .or_else(|| parse_kodi(&json))
+        .ok_or(WebhookError::UnknownFormat)
+}
+
+/// Listens on `address`, calling `on_event` for every finished episode. `on_event` returns the
+/// status code and message to answer with
+pub fn serve(address: &str, mut on_event: impl FnMut(PlaybackEvent) -> (u16, String)) -> Result<(), String> {
+    let server = Server::http(address).map_err(|e| format!("Couldn't listen on {address}: {e}"))?;
+    eprintln!("INFO: Listening for webhooks on http://{}", server.server_addr());
+    for mut request in server.incoming_requests() {
+        let content_type = request
+            .headers()
+            .iter()
+            .find(|header| header.field.equiv("Content-Type"))
+            .map(|header| header.value.to_string());
+        let (status, message) = match api::read_body(request.as_reader()) {
+            Err(e) => (e.status(), e.to_string()),
+            Ok(body) => match parse(&body, content_type.as_deref()) {
+                Ok(Some(event)) => on_event(event),
+                Ok(None) => (202, "Ignored, not a finished episode".to_string()),
+                Err(e) => (400, e.to_string()),
+            },
+        };
+        let response = Response::from_string(message + "\n")
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "text/plain").unwrap());
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JELLYFIN: &str = r#"{"ServerId":"abc","NotificationType":"PlaybackStop","ItemType":"Episode","SeriesName":"Dark","SeasonNumber":1,"SeasonNumber00":"01","EpisodeNumber":3,"EpisodeNumber00":"03","PlayedToCompletion":true}"#;
+    const PLEX: &str = r#"{"event":"media.scrobble","user":true,"owner":true,"Metadata":{"type":"episode","grandparentTitle":"Dark","parentTitle":"Season 1","parentIndex":1,"index":3,"title":"Past and Present"}}"#;
+    const KODI: &str = r#"{"jsonrpc":"2.0","method":"Player.OnStop","params":{"sender":"xbmc","data":{"end":true,"item":{"type":"episode","showtitle":"Dark","season":1,"episode":3}}}}"#;
+
+    fn dark_episode() -> Option<PlaybackEvent> {
+        Some(PlaybackEvent {
+            show: "Dark".to_string(),
+            season: 1,
+            episode: 3,
+        })
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(JELLYFIN.as_bytes(), Some("application/json")).unwrap(), dark_episode());
+        assert_eq!(parse(PLEX.as_bytes(), None).unwrap(), dark_episode());
+        assert_eq!(parse(KODI.as_bytes(), None).unwrap(), dark_episode());
+    }
+
+    #[test]
+    fn test_parse_not_finished() {
+        let jellyfin = JELLYFIN.replace("\"PlayedToCompletion\":true", "\"PlayedToCompletion\":false");
+        assert_eq!(parse(jellyfin.as_bytes(), None).unwrap(), None);
+        let plex = PLEX.replace("media.scrobble", "media.pause");
+        assert_eq!(parse(plex.as_bytes(), None).unwrap(), None);
+        let kodi = KODI.replace("\"end\":true", "\"end\":false");
+        assert_eq!(parse(kodi.as_bytes(), None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(matches!(parse(b"not json", None), Err(WebhookError::MalformedPayload)));
+        assert!(matches!(parse(b"{\"hello\":1}", None), Err(WebhookError::UnknownFormat)));
+    }
+
+    #[test]
+    fn test_parse_plex_multipart() {
+        let body = format!(
+            "--XYZ\r\nContent-Disposition: form-data; name=\"payload\"\r\nContent-Type: application/json\r\n\r\n{PLEX}\r\n--XYZ--\r\n"
+        );
+        let event = parse(body.as_bytes(), Some("multipart/form-data; boundary=XYZ")).unwrap();
+        assert_eq!(event, dark_episode());
+    }
+}
@@ -0,0 +1,161 @@
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    StatusCode, Url,
+};
+use serde::Deserialize;
+
+use crate::{
+    serie::{Kind, Season, Serie},
+    utils,
+};
+
+// wikipedia asks api clients to identify themselves
+const USER_AGENT: &str = concat!("bw/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Debug, Deserialize)]
+struct SearchResponse {
+    pages: Vec<Page>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Page {
+    key: String,
+    title: String,
+    description: Option<String>,
+}
+
+impl Page {
+    #[inline]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    #[inline]
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Summary {
+    title: String,
+    description: Option<String>,
+    thumbnail: Option<Thumbnail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Thumbnail {
+    source: String,
+}
+
+/// Pages are about films when their short description says so, like "2010 film by Christopher Nolan"
+#[inline]
+fn is_film(description: Option<&str>) -> bool {
+    description.is_some_and(|description| {
+        description
+            .split(|c: char| !c.is_alphanumeric())
+            .any(|word| word.eq_ignore_ascii_case("film"))
+    })
+}
+
+/// Sends `request`, returning the status code and body of the response
+fn get(request: RequestBuilder) -> Result<(StatusCode, String), String> {
+    let response = request
+        .header("User-Agent", USER_AGENT)
+        .send()
+        .map_err(|e| format!("Error sending get request: {e}"))?;
+    let status = response.status();
+    let body = response.text().map_err(|e| format!("Error reading the response text: {e}"))?;
+    Ok((status, body))
+}
+
+/// Movies whose title matches `query`
+pub fn search_movies(query: &str) -> Result<Vec<Page>, String> {
+    let request = Client::new()
+        .get("https://en.wikipedia.org/w/rest.php/v1/search/title")
+        .query(&[("q", query), ("limit", "50")]);
+    match get(request)? {
+        (status, body) if status.is_success() => films(&body),
+        (status, _) => Err(format!("Wikipedia answered the search with {status}")),
+    }
+}
+
+fn films(body: &str) -> Result<Vec<Page>, String> {
+    let response: SearchResponse = serde_json::from_str(body).map_err(|e| format!("Error converting json: {e}"))?;
+    Ok(response
+        .pages
+        .into_iter()
+        .filter(|page| is_film(page.description.as_deref()))
+        .collect())
+}
+
+/// The movie of the page `key` (as printed by [`search_movies`]), as a serie of one episode
+pub fn fetch_movie(key: &str) -> Result<Serie, String> {
+    match get(Client::new().get(summary_url(key)))? {
+        (status, body) if status.is_success() => movie(key, &body),
+        (StatusCode::NOT_FOUND, _) => Err(format!("No page with the key \"{key}\"")),
+        (status, _) => Err(format!("Wikipedia answered \"{key}\" with {status}")),
+    }
+}
+
+/// The summary of the page `key`, percent-encoded so it stays a single segment of the path
+fn summary_url(key: &str) -> Url {
+    let mut url = Url::parse("https://en.wikipedia.org/api/rest_v1/page/summary").unwrap();
+    url.path_segments_mut().unwrap().push(key);
+    url
+}
+
+fn movie(key: &str, body: &str) -> Result<Serie, String> {
+    let summary: Summary = serde_json::from_str(body).map_err(|e| format!("Error converting json: {e}"))?;
+    if !is_film(summary.description.as_deref()) {
+        return Err(format!("\"{key}\" isn't a film"));
+    }
+    // titles like "Face/Off" would put the file in a directory
+    let name = summary.title.replace(['/', '\\'], "-");
+    if !utils::is_file_name(&name) {
+        return Err(format!("\"{}\" can't be a file name", summary.title));
+    }
+    let mut serie = Serie::new(vec![Season::new(1)], name);
+    serie.kind = Some(Kind::Movie);
+    serie.poster = summary.thumbnail.map(|thumbnail| thumbnail.source);
+    serie.source = Some(format!("wikipedia:{key}"));
+    Ok(serie)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_films() {
+        let body = r#"{"pages":[
+            {"id":1,"key":"Inception","title":"Inception","description":"2010 film by Christopher Nolan"},
+            {"id":2,"key":"Inception_(soundtrack)","title":"Inception (soundtrack)","description":"2010 soundtrack album"},
+            {"id":3,"key":"Inceptionism","title":"Inceptionism","description":null}
+        ]}"#;
+        let films = films(body).unwrap();
+        assert_eq!(films.len(), 1);
+        assert_eq!(films[0].key(), "Inception");
+    }
+
+    #[test]
+    fn test_summary_url() {
+        assert_eq!(summary_url("Inception").as_str(), "https://en.wikipedia.org/api/rest_v1/page/summary/Inception");
+        assert_eq!(summary_url("AC/DC?x#y").as_str(), "https://en.wikipedia.org/api/rest_v1/page/summary/AC%2FDC%3Fx%23y");
+    }
+
+    #[test]
+    fn test_movie() {
+        let body = r#"{"type":"standard","title":"Inception","description":"2010 film by Christopher Nolan",
+            "thumbnail":{"source":"https://upload.wikimedia.org/inception.jpg","width":220,"height":326}}"#;
+        let serie = movie("Inception", body).unwrap();
+        assert_eq!(serie.name, "Inception");
+        assert_eq!(serie.kind, Some(Kind::Movie));
+        assert_eq!(serie.to_string(), "kind: movie\nposter: https://upload.wikimedia.org/inception.jpg\nsource: wikipedia:Inception\n0/1\n");
+        assert!(movie("Inception", "<html>").is_err());
+        let face_off = movie("Face/Off", r#"{"title":"Face/Off","description":"1997 film by John Woo"}"#).unwrap();
+        assert_eq!(face_off.filename(), "Face-Off.bw");
+        assert!(movie("..", r#"{"title":"..","description":"film"}"#).is_err());
+        assert!(movie("Christopher_Nolan", r#"{"title":"Christopher Nolan","description":"British-American filmmaker"}"#).is_err());
+    }
+}